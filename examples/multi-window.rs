@@ -0,0 +1,27 @@
+use thin_engine::prelude::*;
+fn main() {
+    let event_loop = EventLoop::new().unwrap();
+    event_loop.set_control_flow(ControlFlow::Poll);
+
+    struct Windows { tools: WindowId }
+    thin_engine::builder(input_map!()).with_setup(|_, window, event_loop, settings, _| {
+        window.set_title("Level");
+        let (tools, tools_display) = SimpleWindowBuilder::new()
+            .with_title("Tools")
+            .with_inner_size(300, 400)
+            .build(event_loop);
+        let tools = settings.add_window(tools, tools_display);
+        Ok(Windows { tools })
+    }).with_draw(|windows, _, display, _, _, window, time| {
+        let mut frame = display.draw();
+        // each window is drawn with its own display
+        if window.id() == windows.tools {
+            frame.clear_color(0.2, 0.2, 0.2, 1.0);
+        } else {
+            frame.clear_color(time.elapsed_secs().sin() * 0.5 + 0.5, 0.3, 0.6, 1.0);
+        }
+        window.pre_present_notify();
+        frame.finish()?;
+        Ok(())
+    }).with_fixed_tps(60).build(event_loop).unwrap();
+}
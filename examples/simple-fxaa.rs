@@ -1,4 +1,4 @@
-use std::{f32::consts::PI, rc::Rc, cell::RefCell};
+use std::f32::consts::PI;
 use thin_engine::{
     ResizableTexture2d, ResizableDepthTexture2d,
     prelude::*, glium::framebuffer::*,
@@ -8,7 +8,7 @@ use thin_engine::{
 enum Action {
     Left, Right, Forward, Back,
     LookLeft, LookRight, LookUp, LookDown,
-    FXAA
+    Fxaa
 }
 fn main() {
     use Action::*;
@@ -27,7 +27,7 @@ fn main() {
         (LookLeft,  MouseMoveLeft,  RightStickLeft ),
         (LookUp,    MouseMoveUp,    RightStickUp   ),
         (LookDown,  MouseMoveDown,  RightStickDown ),
-        (FXAA,      KeyF,       GamepadInput::North)
+        (Fxaa,      KeyF,       GamepadInput::North)
     ) };
     struct Graphics {
         screen_indices: IndexBuffer<u32>,
@@ -41,9 +41,6 @@ fn main() {
 
         fxaa: Program, normal: Program, program: Program
     }
-    let graphics: Rc<RefCell<Option<Graphics>>> = Rc::default();
-    let graphics_setup = graphics.clone();
-
     let draw_parameters = DrawParameters {
         backface_culling: draw_parameters::BackfaceCullingMode::CullClockwise,
         ..params::alias_3d()
@@ -52,10 +49,8 @@ fn main() {
     
     let mut pos = vec3(0.0, 0.0, -30.0);
     let mut rot = vec2(0.0, 0.0);
-    
-    let mut frame_start = Instant::now();
 
-    thin_engine::builder(input).with_setup(|display, window, _| {
+    thin_engine::builder(input).with_setup(|display, window, _, _, _| {
         window.set_title("FXAA Test");
         let _ = window.set_cursor_grab(CursorGrabMode::Confined);
         let _ = window.set_cursor_grab(CursorGrabMode::Locked);
@@ -63,10 +58,10 @@ fn main() {
 
         let (screen_indices, screen_vertices, screen_uvs) = mesh!(
             display, &screen::INDICES, &screen::VERTICES, &screen::UVS
-        ).unwrap();
+        )?;
         let (teapot_indices, teapot_vertices, teapot_uvs, teapot_normals) = mesh!(
             display, &teapot::INDICES, &teapot::VERTICES, &[] as &[TextureCoords; 0], &teapot::NORMALS
-        ).unwrap();
+        )?;
 
         let program = Program::from_source(
             display,
@@ -100,8 +95,8 @@ fn main() {
                 float light_level = max(dot(light, v_normal), 0.0);
                 colour = vec4(albedo * light_level + ambient + vec3(specular), 1.0);
             }", None
-        ).unwrap();
-        let fxaa = shaders::fxaa_shader(display).unwrap();
+        )?;
+        let fxaa = shaders::fxaa_shader(display)?;
         let normal = Program::from_source(
             display,
             "#version 140
@@ -119,33 +114,31 @@ fn main() {
             void main() {
                 colour = texture(tex, uv);
             }", None
-        ).unwrap();
-        graphics_setup.replace(Some(Graphics {
+        )?;
+        Ok(Graphics {
             screen_indices, screen_vertices, screen_uvs,
             teapot_indices, teapot_vertices, teapot_uvs, teapot_normals,
             program, normal, fxaa
-        }));
-    }).with_update(|input, display, _, _, _| {
-        let graphics = graphics.borrow();
+        })
+    }).with_update(|graphics, input, display, _, _, _, time| {
         let Graphics {
             screen_indices, screen_vertices, screen_uvs,
             teapot_indices, teapot_vertices, teapot_uvs, teapot_normals,
             program, normal, fxaa
-        } = graphics.as_ref().unwrap();
+        } = &*graphics;
         let teapot_mesh = (teapot_vertices, teapot_normals, teapot_uvs);
         let screen_mesh = (screen_vertices, screen_uvs);
 
-        let delta_time = frame_start.elapsed().as_secs_f32();
-        frame_start = Instant::now();
+        let delta_time = time.delta_secs();
 
         // using a small resolution to better show the effect of fxaa.
         let size = (380, 216);
         display.resize(size);
-        depth.resize_to_display(&display);
-        colour.resize_to_display(&display);
+        depth.resize_to_display(display);
+        colour.resize_to_display(display);
 
         // press f or gamepad north to toggle FXAA
-        if input.pressed(FXAA) { fxaa_on = !fxaa_on }
+        if input.pressed(Fxaa) { fxaa_on = !fxaa_on }
 
         let colour = colour.texture();
         let depth = depth.texture();
@@ -182,13 +175,14 @@ fn main() {
                 shine: 50.0f32,
             },
             &draw_parameters,
-        ).unwrap();
+        )?;
 
         let mut frame = display.draw();
         frame.draw(
             screen_mesh, screen_indices, if fxaa_on { fxaa } else { normal },
             &shaders::fxaa_uniforms(colour), &DrawParameters::default()
-        ).unwrap();
-        frame.finish().unwrap();
+        )?;
+        frame.finish()?;
+        Ok(())
     }).build(event_loop).unwrap();
 }
@@ -1,4 +1,4 @@
-use std::{f32::consts::PI, rc::Rc, cell::RefCell};
+use std::f32::consts::PI;
 use thin_engine::{prelude::*, meshes::teapot};
 #[derive(Hash, PartialEq, Eq, Clone, Copy)]
 enum Action {
@@ -14,7 +14,7 @@ fn main() {
     let input = { use base_input_codes::*; input_map!(
         (Jump,    Space,  GamepadInput::South),
         // square brackets mean that all input codes must be pressed for the bind to be pressed
-        (Exit,    [LeftControl, Escape], GamepadInput::Start),
+        (Exit,    [ControlLeft, Escape], GamepadInput::Start),
         (Left,    ArrowLeft,  KeyA,  LeftStickLeft ),
         (Right,   ArrowRight, KeyD,  LeftStickRight),
         (Forward, ArrowUp,    KeyW,  LeftStickUp   ),
@@ -31,9 +31,6 @@ fn main() {
         vertices: VertexBuffer<Vertex>,
         normals: VertexBuffer<Normal>
     }
-    let graphics: Rc<RefCell<Option<Graphics>>> = Rc::new(RefCell::new(None));
-    let graphics_setup = graphics.clone();
-    
     let draw_parameters = DrawParameters {
         backface_culling: draw_parameters::BackfaceCullingMode::CullClockwise,
         ..params::alias_3d()
@@ -43,9 +40,7 @@ fn main() {
     let mut rot = vec2(0.0, 0.0);
     let mut gravity = 0.0;
 
-    let mut frame_start = Instant::now();
-
-    thin_engine::builder(input).with_setup(|display, window, _| {
+    thin_engine::builder(input).with_setup(|display, window, _, _, _| {
         let _ = window.set_cursor_grab(CursorGrabMode::Confined);
         let _ = window.set_cursor_grab(CursorGrabMode::Locked);
         window.set_cursor_visible(false);
@@ -53,7 +48,7 @@ fn main() {
 
         let (indices, vertices, normals) = mesh!(
             display, &teapot::INDICES, &teapot::VERTICES, &teapot::NORMALS
-        ).unwrap();
+        )?;
         let program = Program::from_source(
             display,
             "#version 140
@@ -81,13 +76,11 @@ fn main() {
                 float light_level = dot(light, v_normal);
                 colour = vec4(albedo * light_level, 1.0);
             }", None,
-        ).unwrap();
-        graphics_setup.replace(Some(Graphics { program, indices, vertices, normals }));
-    }).with_update(|input, display, _, target, _| {
-        let graphics = graphics.borrow();
-        let Graphics { vertices, indices, normals, program } = graphics.as_ref().unwrap();
-        let delta_time = frame_start.elapsed().as_secs_f32();
-        frame_start = Instant::now();
+        )?;
+        Ok(Graphics { program, indices, vertices, normals })
+    }).with_update(|graphics, input, display, _, target, _, time| {
+        let Graphics { vertices, indices, normals, program } = &*graphics;
+        let delta_time = time.delta_secs();
 
         let mut frame = display.draw();
         let perspective = Mat4::perspective_3d(frame.get_dimensions(), 1.0, 1024.0, 0.1);
@@ -123,8 +116,9 @@ fn main() {
                 light: vec3(1.0, -0.9, -1.0).normalise()
             },
             &draw_parameters,
-        ).unwrap();
+        )?;
 
-        frame.finish().unwrap();
+        frame.finish()?;
+        Ok(())
     }).build(event_loop).unwrap();
 }
@@ -1,5 +1,4 @@
 use thin_engine::{text_renderer::*, prelude::*};
-use std::{cell::RefCell, rc::Rc};
 fn main() {
     let event_loop = EventLoop::new().unwrap();
     event_loop.set_control_flow(ControlFlow::Poll);
@@ -10,9 +9,6 @@ fn main() {
         vertices: VertexBuffer<Vertex>,
         uvs: VertexBuffer<TextureCoords>
     }
-    let graphics: Rc<RefCell<Option<Graphics>>> = Rc::default();
-    let graphics_setup = graphics.clone();
-
     let mut font = Font::from_scale_and_file(40.0, "examples/DroidSans.ttf").unwrap();
     let draw_params = DrawParameters {
         blend: glium::Blend::alpha_blending(),
@@ -20,17 +16,14 @@ fn main() {
         ..params::alias_3d()
     };
 
-    let time = std::time::Instant::now();
-
     let text = "Text can be drawn in 2d or 3d thanks to the power of Matrices. Text is drawn without wrapping and tab spacing, however the font struct has a function to format text for you.";
-    thin_engine::builder(input_map!()).with_setup(|display, window, _| {
+    thin_engine::builder(input_map!()).with_setup(|display, window, _, _, _| {
         window.set_title("Text Render");
-        let (indices, vertices, uvs) = Font::mesh(display).unwrap();
-        let shader = Font::shader(display).unwrap();
-        graphics_setup.replace(Some(Graphics { indices, vertices, uvs, shader }));
-    }).with_update(|_input, display, _settings, _target, window| {
-        let graphics = graphics.borrow();
-        let Graphics { shader, vertices, uvs, indices } = graphics.as_ref().unwrap();
+        let (indices, vertices, uvs) = Font::mesh(display)?;
+        let shader = Font::shader(display)?;
+        Ok(Graphics { indices, vertices, uvs, shader })
+    }).with_update(|graphics, _input, display, _settings, _target, window, time| {
+        let Graphics { shader, vertices, uvs, indices } = graphics;
 
         let text_renderer = TextRenderer {
             shader, indices, vertices, uvs, draw_params: &draw_params, display
@@ -51,7 +44,7 @@ fn main() {
         let formated_text = font.format_text(text, Some(width as f32/font_size), 8, display);
         let formated_3d_text = font.format_text(text, Some(10.0), 8, display);
         let pos = vec3(-(width as f32 / height as f32), 1.0, 0.0);
-        let time = time.elapsed().as_secs_f32();
+        let time = time.elapsed_secs();
 
         // 3d text
         text_renderer.draw(
@@ -66,15 +59,16 @@ fn main() {
             ) * Mat4::from_pos(vec3(-5.0, 5.0, 0.0)),
             perspective_3d, Mat4::default(),
             &mut font
-        ).unwrap();
+        )?;
 
         // 2d text
         text_renderer.draw(
             &formated_text, Vec3::ZERO, &mut frame,
             Mat4::from_pos_and_scale(pos, Vec3::splat(0.1)),
             perspective_2d, Mat4::default(), &mut font
-        ).unwrap();
+        )?;
 
-        frame.finish().unwrap();
+        frame.finish()?;
+        Ok(())
     }).build(event_loop).unwrap()
 }
@@ -0,0 +1,29 @@
+use thin_engine::prelude::*;
+/// sent from the loader thread when it is done
+struct Loaded(Vec3);
+fn main() {
+    let event_loop = EventLoop::<Loaded>::with_user_event().build().unwrap();
+
+    struct State { colour: Vec3 }
+    thin_engine::builder(input_map!()).with_setup(|_, window, _, _, proxy: &EventLoopProxy<Loaded>| {
+        window.set_title("User Events");
+        let proxy = proxy.clone();
+        thread::spawn(move || {
+            // pretend to load something slow
+            thread::sleep(Duration::from_secs(2));
+            let _ = proxy.send_event(Loaded(vec3(0.1, 0.7, 0.4)));
+        });
+        Ok(State { colour: vec3(0.2, 0.2, 0.2) })
+    }).with_user_event(|state, Loaded(colour), _, _, window| {
+        state.colour = colour;
+        if let Some((window, _)) = window { window.request_redraw() }
+        Ok(())
+    }).with_draw(|state, _, display, _, _, window, _| {
+        let Vec3 { x, y, z } = state.colour;
+        let mut frame = display.draw();
+        frame.clear_color(x, y, z, 1.0);
+        window.pre_present_notify();
+        frame.finish()?;
+        Ok(())
+    }).build(event_loop).unwrap();
+}
@@ -1,171 +1,804 @@
 #![allow(clippy::type_complexity)]
 use winit::{
     application::ApplicationHandler,
-    window::{Window, WindowId},
-    event_loop::{EventLoop, ActiveEventLoop},
+    window::{Window, WindowId, WindowButtons, Theme, Fullscreen},
+    event_loop::{EventLoop, ActiveEventLoop, EventLoopProxy},
+    dpi::{PhysicalSize, PhysicalPosition},
     event::*
 };
-use crate::{SimpleWindowBuilder, Display, Settings};
-use std::{hash::Hash, time::Instant};
+use crate::{SimpleWindowBuilder, Display, Settings, Time, ThinError, script::{InputScript, ScriptedInputMap}};
+#[cfg(feature = "record")]
+use crate::recording::Recorder;
+#[cfg(all(feature = "headless", not(target_vendor = "apple")))]
+use crate::{headless::{Capture, HeadlessRun}, capture::{Image, read_framebuffer}};
+use std::{hash::Hash, time::{Duration, Instant}, collections::HashMap, cell::RefCell, error::Error};
 use winit_input_map::InputMap;
+use glium::{glutin::config::{ConfigTemplateBuilder, Config}, backend::glutin::simple_window_builder::GliumEventLoop};
+use glutin_winit::DisplayBuilder;
+/// An alternative to the closures of `ThinBuilder` for when your game logic is better structured
+/// as an impl block. All methods have empty default implementations so you only need to write
+/// the ones you use. Returning an error from any method exits the program and the error is
+/// returned from `build_app()`. `E` is the type of user events sent with an `EventLoopProxy`.
+/// Run it with `ThinBuilder::build_app()` or by passing a `ThinEngine` to
+/// `EventLoop::run_app()`.
+///
+/// When the app is suspended `suspended` is run, then resources in `settings.resources` are
+/// released and the window and display are dropped. When it is resumed the window is rebuilt
+/// with the same window settings and config template, then its size, position, title and other
+/// state are restored, the resources are rebuilt and `resumed` is run. Any other GPU
+/// resources become invalid and have to be made again in `resumed`.
+/// ```no_run
+/// use thin_engine::prelude::*;
+/// struct Game { clear_colour: Vec3 }
+/// impl ThinApp<()> for Game {
+///     fn update(
+///         &mut self, _input: &mut InputMap<()>, _display: &Display, _settings: &mut Settings,
+///         _event_loop: &ActiveEventLoop, _window: &mut Window, time: &Time
+///     ) -> Result<(), ThinError> {
+///         self.clear_colour.x = time.elapsed_secs().sin() * 0.5 + 0.5;
+///         Ok(())
+///     }
+///     fn draw(
+///         &mut self, _input: &mut InputMap<()>, display: &Display, _settings: &mut Settings,
+///         _event_loop: &ActiveEventLoop, window: &mut Window, _time: &Time
+///     ) -> Result<(), ThinError> {
+///         let Vec3 { x, y, z } = self.clear_colour;
+///         let mut frame = display.draw();
+///         frame.clear_color(x, y, z, 1.0);
+///         window.pre_present_notify();
+///         frame.finish()?;
+///         Ok(())
+///     }
+/// }
+/// let event_loop = EventLoop::new().unwrap();
+/// let mut game = Game { clear_colour: vec3(0.0, 0.3, 0.6) };
+/// thin_engine::builder(input_map!()).with_fixed_tps(60)
+///     .build_app(event_loop, &mut game).unwrap();
+/// ```
+#[allow(unused_variables)]
+pub trait ThinApp<H: Hash + PartialEq + Eq + Clone + Copy, E: 'static = ()> {
+    /// this is run once when the window and display are first created. extra windows can be
+    /// added with `settings.add_window()` and `proxy` can be cloned to send user events from
+    /// other threads.
+    fn setup(
+        &mut self, display: &Display, window: &mut Window, event_loop: &ActiveEventLoop,
+        settings: &mut Settings, proxy: &EventLoopProxy<E>
+    ) -> Result<(), ThinError> { Ok(()) }
+    /// this is run always. if min_duration in settings is set, then it is throttled to said value.
+    /// if a fixed timestep is set, it is instead run in constant steps of that duration, several
+    /// times a frame if needed to catch up.
+    fn update(
+        &mut self, input: &mut InputMap<H>, display: &Display, settings: &mut Settings,
+        event_loop: &ActiveEventLoop, window: &mut Window, time: &Time
+    ) -> Result<(), ThinError> { Ok(()) }
+    /// this is run whenever a draw request is scheduled. when using a fixed timestep a draw is
+    /// requested every frame and `time.alpha` is how far between the previous and next update
+    /// the frame is. with multiple windows this is run for each window with its own display,
+    /// use `window.id()` to tell them apart.
+    fn draw(
+        &mut self, input: &mut InputMap<H>, display: &Display, settings: &mut Settings,
+        event_loop: &ActiveEventLoop, window: &mut Window, time: &Time
+    ) -> Result<(), ThinError> { Ok(()) }
+    /// recieves window events, device events, about to wait and suspended events after thin
+    /// engine has handled them. `state` is the window the event was sent to or the main window.
+    fn event(
+        &mut self, event: Event<()>, settings: &mut Settings, event_loop: &ActiveEventLoop,
+        state: Option<&(Window, Display)>
+    ) -> Result<(), ThinError> { Ok(()) }
+    /// recieves user events sent with an `EventLoopProxy`. `state` is the main window.
+    fn user_event(
+        &mut self, event: E, settings: &mut Settings, event_loop: &ActiveEventLoop,
+        state: Option<&(Window, Display)>
+    ) -> Result<(), ThinError> { Ok(()) }
+    /// this is run before the window and display are dropped when the app is suspended.
+    fn suspended(&mut self, event_loop: &ActiveEventLoop) -> Result<(), ThinError> { Ok(()) }
+    /// this is run whenever the app is resumed with the new window and display, after setup when
+    /// it first starts and after `settings.resources` are rebuilt otherwise.
+    fn resumed(
+        &mut self, display: &Display, window: &mut Window,
+        event_loop: &ActiveEventLoop, settings: &mut Settings
+    ) -> Result<(), ThinError> { Ok(()) }
+    /// this is run once when the event loop is exiting.
+    fn exit(&mut self, event_loop: &ActiveEventLoop) -> Result<(), ThinError> { Ok(()) }
+}
 /// holds all the data and runs the application.
-pub struct ThinEngine<'a, H, D, S, U, E>
-where H: Hash + PartialEq + Eq + Clone + Copy,
-S: FnMut(&Display, &mut Window, &ActiveEventLoop),
-U: FnMut(&mut InputMap<H>, &Display, &mut Settings, &ActiveEventLoop, &mut Window),
-D: FnMut(&mut InputMap<H>, &Display, &mut Settings, &ActiveEventLoop, &mut Window),
-E: FnMut(Event<()>, &ActiveEventLoop, Option<&(Window, Display)>)
-{
+pub struct ThinEngine<'a, H, A, E = ()>
+where H: Hash + PartialEq + Eq + Clone + Copy, A: ThinApp<H, E>, E: 'static {
     state: Option<(Window, Display)>,
+    /// the settings the first window is built with.
     window_settings: Option<SimpleWindowBuilder>,
-    update:        &'a mut U,
-    draw:          &'a mut D,
-    setup:         &'a mut S,
-    event_handler: &'a mut E,
+    /// replaces the config template of `window_settings` if set.
+    config_template: Option<ConfigTemplateBuilder>,
+    /// the display builder and config template of the first window, used to rebuild it.
+    kept_settings: Option<(DisplayBuilder, ConfigTemplateBuilder)>,
+    /// the state of the window when it was last suspended, restored when it is rebuilt.
+    window_state: Option<WindowState>,
+    /// set once setup has been run.
+    started: bool,
+    app: &'a mut A,
     input_map: InputMap<H>,
     settings: Settings,
     frame_start: Instant,
+    last_step: Instant,
+    accumulator: Duration,
+    time: Time,
+    error: Option<ThinError>,
+    /// windows added with `Settings::add_window()`
+    windows: HashMap<WindowId, (Window, Display)>,
+    proxy: EventLoopProxy<E>,
+    replay: Option<Replay>,
+    #[cfg(feature = "record")]
+    recorder: Option<Recorder>,
+    #[cfg(all(feature = "headless", not(target_vendor = "apple")))]
+    headless: Option<HeadlessFrames>,
+}
+/// a script being replayed in place of real input.
+struct Replay {
+    input: ScriptedInputMap,
+    script: InputScript,
+    /// the index of the next input to send.
+    next: usize
+}
+/// the frames to run and read back in a headless run.
+#[cfg(all(feature = "headless", not(target_vendor = "apple")))]
+struct HeadlessFrames {
+    frames: u64,
+    capture: Capture,
+    captured: Vec<(u64, Image)>
+}
+impl<'a, H, A, E> ThinEngine<'a, H, A, E>
+where H: Hash + PartialEq + Eq + Clone + Copy, A: ThinApp<H, E>, E: 'static {
+    /// `proxy` is given to setup and can be made with `EventLoop::create_proxy()`.
+    pub fn new(
+        app: &'a mut A, input_map: InputMap<H>, settings: Settings,
+        window_settings: SimpleWindowBuilder, proxy: EventLoopProxy<E>
+    ) -> Self {
+        Self {
+            state: None,
+            window_settings: Some(window_settings),
+            config_template: None,
+            kept_settings: None,
+            window_state: None,
+            started: false,
+            app, input_map, settings,
+            frame_start: Instant::now(),
+            last_step:   Instant::now(),
+            accumulator: Duration::ZERO,
+            time: Time::default(),
+            error: None,
+            windows: HashMap::new(),
+            proxy,
+            replay: None,
+            #[cfg(feature = "record")]
+            recorder: None,
+            #[cfg(all(feature = "headless", not(target_vendor = "apple")))]
+            headless: None,
+        }
+    }
+    /// sets the config template used to pick the display's config, such as the amount of
+    /// samples, replacing the one set on the window settings.
+    pub fn with_config_template(mut self, config_template: ConfigTemplateBuilder) -> Self {
+        self.config_template = Some(config_template);
+        self
+    }
+    /// replaces input from real devices with a script, such as a recording loaded with
+    /// `InputScript::load()`. update is run once a frame with a fixed clock of the fixed
+    /// timestep, or 1/60th of a second, and the program exits at the end of the script. the
+    /// binds of the input map are changed to send the script so they can't be changed while
+    /// replaying.
+    pub fn with_replay(mut self, mut script: InputScript) -> Self {
+        script.sort();
+        let input = ScriptedInputMap::new(&mut self.input_map);
+        self.replay = Some(Replay { input, script, next: 0 });
+        self
+    }
+    /// records input to a file so it can be loaded with `InputScript::load()` and replayed.
+    #[cfg(feature = "record")]
+    pub fn with_recording(mut self, path: impl AsRef<std::path::Path>) -> Result<Self, ThinError> {
+        self.recorder = Some(Recorder::new(path)?);
+        Ok(self)
+    }
+    /// stops the program after `frames` frames, reading back the frames chosen by `capture`
+    /// once they are finished. if no replay is set an empty script is replayed so the clock is
+    /// simulated. see `ThinBuilder::build_headless()`
+    #[cfg(all(feature = "headless", not(target_vendor = "apple")))]
+    pub fn with_headless(mut self, frames: u64, capture: Capture) -> Self {
+        if self.replay.is_none() { self = self.with_replay(InputScript::new()) }
+        self.headless = Some(HeadlessFrames { frames, capture, captured: Vec::new() });
+        self
+    }
+    /// takes the frames read back in a headless run and their frame numbers.
+    #[cfg(all(feature = "headless", not(target_vendor = "apple")))]
+    pub fn take_frames(&mut self) -> Vec<(u64, Image)> {
+        self.headless.as_mut().map(|i| std::mem::take(&mut i.captured)).unwrap_or_default()
+    }
+    /// the time data of the last update.
+    pub fn time(&self) -> &Time { &self.time }
+    /// takes the first error returned by the app, if there was one.
+    pub fn take_error(&mut self) -> Option<ThinError> {
+        self.error.take()
+    }
+    /// moves windows added or closed through settings in or out of the engine.
+    fn sync_windows(&mut self) {
+        for (window, display) in self.settings.new_windows.drain(..) {
+            window.request_redraw();
+            self.windows.insert(window.id(), (window, display));
+        }
+        for id in self.settings.closed_windows.drain(..) { self.windows.remove(&id); }
+    }
+}
+/// builds a window through a `SimpleWindowBuilder`, keeping the display builder and config template
+/// it was built with as glium has no way to read them back. once kept they are used in place of
+/// the builder's own so the window can be rebuilt exactly the same.
+struct KeepWindowSettings<'a> {
+    event_loop: &'a ActiveEventLoop,
+    kept: RefCell<Option<(DisplayBuilder, ConfigTemplateBuilder)>>
+}
+impl GliumEventLoop for KeepWindowSettings<'_> {
+    fn build<Picker>(
+        &self, display_builder: DisplayBuilder,
+        template_builder: ConfigTemplateBuilder, config_picker: Picker
+    ) -> Result<(Option<Window>, Config), Box<dyn Error>>
+    where Picker: FnOnce(Box<dyn Iterator<Item = Config> + '_>) -> Config {
+        let (display_builder, template_builder) = self.kept.borrow_mut()
+            .get_or_insert((display_builder, template_builder)).clone();
+        self.event_loop.build(display_builder, template_builder, config_picker)
+    }
+}
+/// the parts of a window that can change after it is built, read when it is suspended.
+struct WindowState {
+    inner_size: PhysicalSize<u32>,
+    position: Option<PhysicalPosition<i32>>,
+    resizable: bool,
+    enabled_buttons: WindowButtons,
+    title: String,
+    fullscreen: Option<Fullscreen>,
+    maximized: bool,
+    visible: bool,
+    decorated: bool,
+    theme: Option<Theme>,
+    resize_increments: Option<PhysicalSize<u32>>
 }
-impl<H, D, S, U, E> ApplicationHandler for ThinEngine<'_, H, D, S, U, E>
-where H: Hash + PartialEq + Eq + Clone + Copy,
-S: FnMut(&Display, &mut Window, &ActiveEventLoop),
-U: FnMut(&mut InputMap<H>, &Display, &mut Settings, &ActiveEventLoop, &mut Window),
-D: FnMut(&mut InputMap<H>, &Display, &mut Settings, &ActiveEventLoop, &mut Window),
-E: FnMut(Event<()>, &ActiveEventLoop, Option<&(Window, Display)>)
-{
+impl WindowState {
+    fn read(window: &Window) -> Self {
+        Self {
+            inner_size: window.inner_size(),
+            position: window.outer_position().ok(),
+            resizable: window.is_resizable(),
+            enabled_buttons: window.enabled_buttons(),
+            title: window.title(),
+            fullscreen: window.fullscreen(),
+            maximized: window.is_maximized(),
+            visible: window.is_visible().unwrap_or(true),
+            decorated: window.is_decorated(),
+            theme: window.theme(),
+            resize_increments: window.resize_increments()
+        }
+    }
+    fn restore(self, window: &Window) {
+        let _ = window.request_inner_size(self.inner_size);
+        if let Some(position) = self.position { window.set_outer_position(position) }
+        window.set_resizable(self.resizable);
+        window.set_enabled_buttons(self.enabled_buttons);
+        window.set_title(&self.title);
+        window.set_fullscreen(self.fullscreen);
+        window.set_maximized(self.maximized);
+        window.set_visible(self.visible);
+        window.set_decorations(self.decorated);
+        window.set_theme(self.theme);
+        window.set_resize_increments(self.resize_increments);
+    }
+}
+/// stores the first error and exits so that the error can be returned after the event loop ends.
+fn exit_on_err(error: &mut Option<ThinError>, result: Result<(), ThinError>, event_loop: &ActiveEventLoop) {
+    let Err(err) = result else { return };
+    if error.is_none() { *error = Some(err) }
+    event_loop.exit();
+}
+impl<H, A, E> ApplicationHandler<E> for ThinEngine<'_, H, A, E>
+where H: Hash + PartialEq + Eq + Clone + Copy, A: ThinApp<H, E>, E: 'static {
    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         if self.state.is_some() { return }
-        let (mut window, display) = self.window_settings
-            .take().expect("No window settings are available")
-            .build(event_loop);
-       (self.setup)(&display, &mut window, event_loop);
+        let mut window_settings = self.window_settings.take().unwrap_or_else(SimpleWindowBuilder::new);
+        if let Some(config_template) = self.config_template.take() {
+            window_settings = window_settings.with_config_template_builder(config_template);
+        }
+        let keep = KeepWindowSettings { event_loop, kept: RefCell::new(self.kept_settings.take()) };
+        let (mut window, display) = window_settings.build(&keep);
+        self.kept_settings = keep.kept.into_inner();
+        if let Some(window_state) = self.window_state.take() { window_state.restore(&window) }
+        let first_start = !self.started;
+        let result = if self.started {
+            self.settings.resources.recreate(&display)
+                .and_then(|_| self.settings.assets.recreate(&display))
+        } else {
+            self.started = true;
+            self.app.setup(&display, &mut window, event_loop, &mut self.settings, &self.proxy)
+        };
         self.state = Some((window, display));
+        exit_on_err(&mut self.error, result, event_loop);
+        self.sync_windows();
+        if self.error.is_some() { return }
+        let Some((window, display)) = &mut self.state else { return };
+        let result = self.app.resumed(display, window, event_loop, &mut self.settings);
+        exit_on_err(&mut self.error, result, event_loop);
+        self.sync_windows();
+        // the clock starts after setup so the first delta doesn't include building the window
+        // and loading the game
+        if first_start {
+            self.frame_start = Instant::now();
+            self.last_step = self.frame_start;
+        }
     }
     fn window_event(&mut self, event_loop: &ActiveEventLoop, window_id: WindowId, event: WindowEvent) {
+        let extra = self.windows.contains_key(&window_id);
+        #[cfg(feature = "record")]
+        if let Some(recorder) = &mut self.recorder {
+            let result = recorder.window_event(self.time.frame, &event);
+            exit_on_err(&mut self.error, result, event_loop);
+        }
+        let target = match self.windows.get_mut(&window_id) {
+            Some(target) => Some(target),
+            None => self.state.as_mut()
+        };
         match event {
-            WindowEvent::Resized(size) => self.state.as_mut().unwrap().1.resize(size.into()),
+            WindowEvent::Resized(size) => if let Some((_, display)) = target { display.resize(size.into()) },
+            // closing an extra window only removes it, closing the main window exits
+            WindowEvent::CloseRequested if extra => self.settings.close_window(window_id),
             WindowEvent::CloseRequested => event_loop.exit(),
             WindowEvent::RedrawRequested => {
-                let Some((ref mut window, ref display)) = self.state else { return };
+                let Some((window, display)) = target else { return };
+                if self.error.is_some() { return }
                 let input = &mut self.input_map;
                 let settings = &mut self.settings;
-                (self.draw)(input, display, settings, event_loop, window)
+                let result = self.app.draw(input, display, settings, event_loop, window, &self.time);
+                exit_on_err(&mut self.error, result, event_loop);
             },
-            _ => self.input_map.update_with_window_event(&event)
+            // real input is ignored while replaying
+            _ => if self.replay.is_none() { self.input_map.update_with_window_event(&event) }
         }
-        (self.event_handler)(Event::WindowEvent { window_id, event }, event_loop, self.state.as_ref());
+        let target = match self.windows.get(&window_id) {
+            Some(target) => Some(target),
+            None => self.state.as_ref()
+        };
+        let result = self.app.event(Event::WindowEvent { window_id, event }, &mut self.settings, event_loop, target);
+        exit_on_err(&mut self.error, result, event_loop);
+        self.sync_windows();
+    }
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: E) {
+        let result = self.app.user_event(event, &mut self.settings, event_loop, self.state.as_ref());
+        exit_on_err(&mut self.error, result, event_loop);
+        self.sync_windows();
     }
     fn device_event(&mut self, event_loop: &ActiveEventLoop, device_id: DeviceId, event: DeviceEvent) {
-        self.input_map.update_with_device_event(device_id, &event);
-        (self.event_handler)(Event::DeviceEvent { device_id, event }, event_loop, self.state.as_ref());
+        #[cfg(feature = "record")]
+        if let Some(recorder) = &mut self.recorder {
+            let result = recorder.device_event(self.time.frame, &event);
+            exit_on_err(&mut self.error, result, event_loop);
+        }
+        if self.replay.is_none() { self.input_map.update_with_device_event(device_id, &event) }
+        let event = Event::DeviceEvent { device_id, event };
+        let result = self.app.event(event, &mut self.settings, event_loop, self.state.as_ref());
+        exit_on_err(&mut self.error, result, event_loop);
     }
     fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
-        if let Some(ref mut gilrs) = self.settings.gamepads { self.input_map.update_with_gilrs(gilrs) }
+        if let Some(ref mut gilrs) = self.settings.gamepads {
+            // real input is ignored while replaying
+            if self.replay.is_some() { while gilrs.next_event().is_some() {} }
+            else { self.input_map.update_with_gilrs(gilrs) }
+        }
+        #[cfg(feature = "record")]
+        if let Some(recorder) = &mut self.recorder {
+            let (frame, press_sensitivity) = (self.time.frame, self.input_map.press_sensitivity);
+            let result = self.settings.gamepads.as_ref()
+                .map_or(Ok(()), |gilrs| recorder.gamepads(frame, gilrs, press_sensitivity))
+                .and_then(|_| recorder.flush());
+            exit_on_err(&mut self.error, result, event_loop);
+        }
 
-        let update = self.settings.min_frame_duration
+        let frame = self.settings.min_frame_duration
             .map(|i| i <= self.frame_start.elapsed())
             .unwrap_or(true);
-        if update {
-            let (window, display) = self.state.as_mut().unwrap();
-            (self.update)(&mut self.input_map, display, &mut self.settings, event_loop, window);
-            self.frame_start = Instant::now();
+        let Some((window, display)) = &mut self.state else { return };
+        if self.error.is_some() { return }
+        if let Some(replay) = &mut self.replay {
+            if frame {
+                #[cfg(all(feature = "headless", not(target_vendor = "apple")))]
+                if let Some(headless) = &mut self.headless {
+                    // the last frame was drawn and presented after its update
+                    let last = self.time.frame.checked_sub(1)
+                        .filter(|i| headless.capture.includes(*i, headless.frames));
+                    if let Some(last) = last {
+                        match read_framebuffer(display) {
+                            Ok(image) => headless.captured.push((last, image)),
+                            Err(err) => return exit_on_err(&mut self.error, Err(err), event_loop)
+                        }
+                    }
+                    if self.time.frame >= headless.frames { return event_loop.exit() }
+                }
+                if replay.script.end().is_some_and(|end| self.time.frame >= end) { return event_loop.exit() }
+                let inputs = replay.script.inputs();
+                while let Some((_, input)) = inputs.get(replay.next).filter(|(i, _)| *i <= self.time.frame) {
+                    replay.input.send(&mut self.input_map, input);
+                    replay.next += 1;
+                }
+                let now = Instant::now();
+                let step = self.settings.fixed_timestep.unwrap_or(Duration::from_secs_f64(1.0 / 60.0));
+                self.time.step(step, self.settings.time_scale);
+                self.time.measure_fps(now - self.frame_start);
+                self.time.alpha = 0.0;
+                let result = self.app.update(&mut self.input_map, display, &mut self.settings, event_loop, window, &self.time);
+                exit_on_err(&mut self.error, result, event_loop);
+                self.time.frame += 1;
+                self.frame_start = now;
+                self.input_map.init();
+                if self.settings.fixed_timestep.is_some() { window.request_redraw() }
+                for (window, _) in self.windows.values() { window.request_redraw() }
+            }
+        } else if let Some(step) = self.settings.fixed_timestep {
+            let now = Instant::now();
+            let max_catch_up = step * self.settings.max_fixed_steps.max(1);
+            self.accumulator = (self.accumulator + (now - self.last_step)).min(max_catch_up);
+            self.last_step = now;
+
+            while self.accumulator >= step && self.error.is_none() {
+                self.time.step(step, self.settings.time_scale);
+                let result = self.app.update(&mut self.input_map, display, &mut self.settings, event_loop, window, &self.time);
+                exit_on_err(&mut self.error, result, event_loop);
+                self.time.frame += 1;
+                self.input_map.init();
+                self.accumulator -= step;
+            }
+            self.time.alpha = self.accumulator.as_secs_f32() / step.as_secs_f32();
+            if frame {
+                self.time.measure_fps(now - self.frame_start);
+                window.request_redraw();
+                for (window, _) in self.windows.values() { window.request_redraw() }
+                self.frame_start = now;
+            }
+        } else if frame {
+            let now = Instant::now();
+            let delta = now - self.frame_start;
+            self.time.step(delta, self.settings.time_scale);
+            self.time.measure_fps(delta);
+            self.time.alpha = 0.0;
+            let result = self.app.update(&mut self.input_map, display, &mut self.settings, event_loop, window, &self.time);
+            exit_on_err(&mut self.error, result, event_loop);
+            self.time.frame += 1;
+            self.frame_start = now;
             self.input_map.init();
+            // the main window is drawn in update without a fixed timestep but extra windows are
+            // only drawn in draw
+            for (window, _) in self.windows.values() { window.request_redraw() }
         }
-        (self.event_handler)(Event::AboutToWait, event_loop, self.state.as_ref());
+        let result = self.app.event(Event::AboutToWait, &mut self.settings, event_loop, self.state.as_ref());
+        exit_on_err(&mut self.error, result, event_loop);
+        self.sync_windows();
+        self.settings.assets.unload_unused();
+        if let Some((_, display)) = &self.state { self.settings.assets.hot_reload(display) }
     }
     fn suspended(&mut self, event_loop: &ActiveEventLoop) {
-        let window = self.state.take().unwrap().0;
-
-        self.window_settings = Some(SimpleWindowBuilder::new()
-            .set_window_builder(Window::default_attributes()
-            .with_inner_size(window.inner_size())
-            // todo position
-            .with_resizable(window.is_resizable())
-            .with_enabled_buttons(window.enabled_buttons())
-            .with_title(window.title())
-            .with_fullscreen(window.fullscreen())
-            .with_maximized(window.is_maximized())
-            .with_visible(window.is_visible().unwrap_or(true))
-            // todo transparent
-            .with_decorations(window.is_decorated())
-            .with_theme(window.theme())
-            // todo resize increments
-            // todo parrent window
-        ));
-        (self.event_handler)(Event::Suspended, event_loop, self.state.as_ref());
-    }
-}
-/// holds data used to build and run the program
-pub struct ThinBuilder<'a, H: Hash + PartialEq + Eq + Clone + Copy> {
+        let result = self.app.suspended(event_loop);
+        exit_on_err(&mut self.error, result, event_loop);
+        // sent while the main window still exists
+        let result = self.app.event(Event::Suspended, &mut self.settings, event_loop, self.state.as_ref());
+        exit_on_err(&mut self.error, result, event_loop);
+        // extra windows are dropped and can be added again in resumed
+        self.windows.clear();
+        // resources have to be dropped before the display they were made with
+        self.settings.resources.release();
+        self.settings.assets.release();
+        let Some((window, _)) = self.state.take() else { return };
+        // attributes that can't be read from the window, such as transparency and the parent
+        // window, are kept in `kept_settings` from when the window was first built
+        self.window_state = Some(WindowState::read(&window));
+    }
+    fn exiting(&mut self, event_loop: &ActiveEventLoop) {
+        let result = self.app.exit(event_loop);
+        exit_on_err(&mut self.error, result, event_loop);
+        #[cfg(feature = "record")]
+        if let Some(recorder) = &mut self.recorder {
+            let result = recorder.finish(self.time.frame);
+            exit_on_err(&mut self.error, result, event_loop);
+        }
+    }
+}
+/// runs the callbacks of a `ThinBuilder`, holding the state once setup has made it.
+struct ClosureApp<'a, H: Hash + PartialEq + Eq + Clone + Copy, T, E: 'static> {
+    state: Option<T>,
+    update: Box<dyn FnMut(&mut T, &mut InputMap<H>, &Display, &mut Settings, &ActiveEventLoop, &mut Window, &Time) -> Result<(), ThinError> + 'a>,
+    setup: Box<dyn FnMut(&Display, &mut Window, &ActiveEventLoop, &mut Settings, &EventLoopProxy<E>) -> Result<T, ThinError> + 'a>,
+    draw: Box<dyn FnMut(&mut T, &mut InputMap<H>, &Display, &mut Settings, &ActiveEventLoop, &mut Window, &Time) -> Result<(), ThinError> + 'a>,
+    event_handler: Box<dyn FnMut(&mut T, Event<()>, &mut Settings, &ActiveEventLoop, Option<&(Window, Display)>) -> Result<(), ThinError> + 'a>,
+    user_event: Box<dyn FnMut(&mut T, E, &mut Settings, &ActiveEventLoop, Option<&(Window, Display)>) -> Result<(), ThinError> + 'a>,
+    suspended: Box<dyn FnMut(&mut T, &ActiveEventLoop) -> Result<(), ThinError> + 'a>,
+    resumed: Box<dyn FnMut(&mut T, &Display, &mut Window, &ActiveEventLoop, &mut Settings) -> Result<(), ThinError> + 'a>
+}
+impl<H: Hash + PartialEq + Eq + Clone + Copy, T, E: 'static> ThinApp<H, E> for ClosureApp<'_, H, T, E> {
+    fn setup(
+        &mut self, display: &Display, window: &mut Window, event_loop: &ActiveEventLoop,
+        settings: &mut Settings, proxy: &EventLoopProxy<E>
+    ) -> Result<(), ThinError> {
+        self.state = Some((self.setup)(display, window, event_loop, settings, proxy)?);
+        Ok(())
+    }
+    fn update(
+        &mut self, input: &mut InputMap<H>, display: &Display, settings: &mut Settings,
+        event_loop: &ActiveEventLoop, window: &mut Window, time: &Time
+    ) -> Result<(), ThinError> {
+        let Some(state) = &mut self.state else { return Ok(()) };
+        (self.update)(state, input, display, settings, event_loop, window, time)
+    }
+    fn draw(
+        &mut self, input: &mut InputMap<H>, display: &Display, settings: &mut Settings,
+        event_loop: &ActiveEventLoop, window: &mut Window, time: &Time
+    ) -> Result<(), ThinError> {
+        let Some(state) = &mut self.state else { return Ok(()) };
+        (self.draw)(state, input, display, settings, event_loop, window, time)
+    }
+    fn event(
+        &mut self, event: Event<()>, settings: &mut Settings, event_loop: &ActiveEventLoop,
+        window: Option<&(Window, Display)>
+    ) -> Result<(), ThinError> {
+        let Some(state) = &mut self.state else { return Ok(()) };
+        (self.event_handler)(state, event, settings, event_loop, window)
+    }
+    fn user_event(
+        &mut self, event: E, settings: &mut Settings, event_loop: &ActiveEventLoop,
+        window: Option<&(Window, Display)>
+    ) -> Result<(), ThinError> {
+        let Some(state) = &mut self.state else { return Ok(()) };
+        (self.user_event)(state, event, settings, event_loop, window)
+    }
+    fn suspended(&mut self, event_loop: &ActiveEventLoop) -> Result<(), ThinError> {
+        let Some(state) = &mut self.state else { return Ok(()) };
+        (self.suspended)(state, event_loop)
+    }
+    fn resumed(
+        &mut self, display: &Display, window: &mut Window,
+        event_loop: &ActiveEventLoop, settings: &mut Settings
+    ) -> Result<(), ThinError> {
+        let Some(state) = &mut self.state else { return Ok(()) };
+        (self.resumed)(state, display, window, event_loop, settings)
+    }
+}
+/// the parts of a `ThinBuilder` used to make a `ThinEngine`.
+struct EngineParts<H: Hash + PartialEq + Eq + Clone + Copy> {
+    window_settings: SimpleWindowBuilder,
+    config_template: Option<ConfigTemplateBuilder>,
+    input_map: InputMap<H>,
+    settings: Settings,
+    replay: Option<InputScript>,
+    #[cfg(feature = "record")]
+    recording: Option<std::path::PathBuf>
+}
+impl<H: Hash + PartialEq + Eq + Clone + Copy> EngineParts<H> {
+    fn engine<A: ThinApp<H, E>, E: 'static>(self, app: &mut A, proxy: EventLoopProxy<E>) -> Result<ThinEngine<'_, H, A, E>, ThinError> {
+        let mut engine = ThinEngine::new(app, self.input_map, self.settings, self.window_settings, proxy);
+        if let Some(config_template) = self.config_template { engine = engine.with_config_template(config_template) }
+        if let Some(script) = self.replay { engine = engine.with_replay(script) }
+        #[cfg(feature = "record")]
+        if let Some(path) = self.recording { engine = engine.with_recording(path)? }
+        Ok(engine)
+    }
+}
+/// holds data used to build and run the program. `T` is the state returned by setup which is
+/// passed to all other callbacks and `E` is the type of user events, inferred from the event loop.
+pub struct ThinBuilder<'a, H: Hash + PartialEq + Eq + Clone + Copy, T = (), E: 'static = ()> {
     window_settings: SimpleWindowBuilder,
+    config_template: Option<ConfigTemplateBuilder>,
     input_map: InputMap<H>,
     settings: Settings,
-    update: Box<dyn FnMut(&mut InputMap<H>, &Display, &mut Settings, &ActiveEventLoop, &mut Window) + 'a>,
-    setup: Box<dyn FnMut(&Display, &mut Window, &ActiveEventLoop) + 'a>,
-    draw: Box<dyn FnMut(&mut InputMap<H>, &Display, &mut Settings, &ActiveEventLoop, &mut Window) + 'a>,
-    event_handler: Box<dyn FnMut(Event<()>, &ActiveEventLoop, Option<&(Window, Display)>) + 'a>
+    replay: Option<InputScript>,
+    #[cfg(feature = "record")]
+    recording: Option<std::path::PathBuf>,
+    update: Box<dyn FnMut(&mut T, &mut InputMap<H>, &Display, &mut Settings, &ActiveEventLoop, &mut Window, &Time) -> Result<(), ThinError> + 'a>,
+    setup: Box<dyn FnMut(&Display, &mut Window, &ActiveEventLoop, &mut Settings, &EventLoopProxy<E>) -> Result<T, ThinError> + 'a>,
+    draw: Box<dyn FnMut(&mut T, &mut InputMap<H>, &Display, &mut Settings, &ActiveEventLoop, &mut Window, &Time) -> Result<(), ThinError> + 'a>,
+    event_handler: Box<dyn FnMut(&mut T, Event<()>, &mut Settings, &ActiveEventLoop, Option<&(Window, Display)>) -> Result<(), ThinError> + 'a>,
+    user_event: Box<dyn FnMut(&mut T, E, &mut Settings, &ActiveEventLoop, Option<&(Window, Display)>) -> Result<(), ThinError> + 'a>,
+    suspended: Box<dyn FnMut(&mut T, &ActiveEventLoop) -> Result<(), ThinError> + 'a>,
+    resumed: Box<dyn FnMut(&mut T, &Display, &mut Window, &ActiveEventLoop, &mut Settings) -> Result<(), ThinError> + 'a>
 }
-impl<'a, H: Hash + PartialEq + Eq + Clone + Copy> ThinBuilder<'a, H> {
-   pub fn new(input_map: InputMap<H>) -> ThinBuilder<'a, H> {
+impl<'a, H: Hash + PartialEq + Eq + Clone + Copy, E: 'static> ThinBuilder<'a, H, (), E> {
+   pub fn new(input_map: InputMap<H>) -> ThinBuilder<'a, H, (), E> {
         ThinBuilder {
             input_map,
             settings: Settings::default(),
-            update:        Box::new(|_, _, _, _, _| {}),
-            setup:         Box::new(|_, _, _|       {}),
-            draw:          Box::new(|_, _, _, _, _| {}),
-            event_handler: Box::new(|_, _, _|       {}),
-            window_settings: SimpleWindowBuilder::new()
+            replay: None,
+            #[cfg(feature = "record")]
+            recording: None,
+            update:        Box::new(|_, _, _, _, _, _, _| Ok(())),
+            setup:         Box::new(|_, _, _, _, _|       Ok(())),
+            draw:          Box::new(|_, _, _, _, _, _, _| Ok(())),
+            event_handler: Box::new(|_, _, _, _, _|       Ok(())),
+            user_event:    Box::new(|_, _, _, _, _|       Ok(())),
+            suspended:     Box::new(|_, _|                Ok(())),
+            resumed:       Box::new(|_, _, _, _, _|       Ok(())),
+            window_settings: SimpleWindowBuilder::new(),
+            config_template: None
+        }
+    }
+    /// this is run once when the window and display are first created. the returned state is
+    /// passed to all other callbacks. callbacks that were set before this ignore the state.
+    /// extra windows can be added with `settings.add_window()` and `proxy` can be cloned to send
+    /// user events from other threads.
+    pub fn with_setup<T: 'a>(
+        self,
+        setup: impl FnMut(&Display, &mut Window, &ActiveEventLoop, &mut Settings, &EventLoopProxy<E>) -> Result<T, ThinError> + 'a
+    ) -> ThinBuilder<'a, H, T, E> where H: 'a {
+        let Self {
+            window_settings, config_template, input_map, settings, replay,
+            #[cfg(feature = "record")] recording,
+            mut update, mut draw, mut event_handler, mut user_event, mut suspended, mut resumed, ..
+        } = self;
+        ThinBuilder {
+            window_settings, config_template, input_map, settings, replay,
+            #[cfg(feature = "record")] recording,
+            setup: Box::new(setup),
+            update: Box::new(move |_, i, d, s, e, w, t| update(&mut (), i, d, s, e, w, t)),
+            draw:   Box::new(move |_, i, d, s, e, w, t| draw(&mut (), i, d, s, e, w, t)),
+            event_handler: Box::new(move |_, ev, s, e, w| event_handler(&mut (), ev, s, e, w)),
+            user_event:    Box::new(move |_, ev, s, e, w| user_event(&mut (), ev, s, e, w)),
+            suspended:     Box::new(move |_, e| suspended(&mut (), e)),
+            resumed:       Box::new(move |_, d, w, e, s| resumed(&mut (), d, w, e, s))
         }
     }
-    /// builds and runs the program
-    pub fn build(mut self, ev: EventLoop<()>) -> Result<(), winit::error::EventLoopError> {
-        let mut engine = ThinEngine {
+    /// builds and runs the program using the methods of `app` instead of callbacks. the input
+    /// map, settings and window settings of the builder are still used. returns the first
+    /// error returned by `app`.
+    pub fn build_app(self, ev: EventLoop<E>, app: &mut impl ThinApp<H, E>) -> Result<(), ThinError> {
+        let (parts, _) = self.split();
+        let mut engine = parts.engine(app, ev.create_proxy())?;
+        ev.run_app(&mut engine)?;
+        engine.take_error().map_or(Ok(()), Err)
+    }
+    /// runs `app` like `build_app()` for a fixed number of frames with a simulated clock. see
+    /// `build_headless()`
+    #[cfg(all(feature = "headless", not(target_vendor = "apple")))]
+    pub fn build_app_headless(
+        self, ev: EventLoop<E>, app: &mut impl ThinApp<H, E>, frames: u64, capture: Capture
+    ) -> Result<HeadlessRun<()>, ThinError> {
+        let (parts, _) = self.split();
+        let mut engine = parts.engine(app, ev.create_proxy())?.with_headless(frames, capture);
+        ev.run_app(&mut engine)?;
+        if let Some(err) = engine.take_error() { return Err(err) }
+        Ok(HeadlessRun { state: (), frames: engine.take_frames(), time: *engine.time() })
+    }
+}
+impl<'a, H: Hash + PartialEq + Eq + Clone + Copy, T, E: 'static> ThinBuilder<'a, H, T, E> {
+    /// builds and runs the program. if a callback returns an error the program exits and the
+    /// first error is returned.
+    pub fn build(self, ev: EventLoop<E>) -> Result<(), ThinError> {
+        let (parts, mut app) = self.split();
+        let mut engine = parts.engine(&mut app, ev.create_proxy())?;
+        ev.run_app(&mut engine)?;
+        engine.take_error().map_or(Ok(()), Err)
+    }
+    /// builds and runs the program for `frames` frames, returning the state from setup and the
+    /// frames chosen by `capture`. update is run once a frame with a simulated clock like
+    /// `with_replay()`, using the replay script as input if one was set, so the same program
+    /// can be tested in CI. a real window is made so a display is needed, use a virtual one
+    /// such as `xvfb-run` on machines without one or `headless::builder()` to run without any.
+    #[cfg(all(feature = "headless", not(target_vendor = "apple")))]
+    pub fn build_headless(self, ev: EventLoop<E>, frames: u64, capture: Capture) -> Result<HeadlessRun<T>, ThinError> {
+        let (parts, mut app) = self.split();
+        let mut engine = parts.engine(&mut app, ev.create_proxy())?.with_headless(frames, capture);
+        ev.run_app(&mut engine)?;
+        if let Some(err) = engine.take_error() { return Err(err) }
+        let (frames, time) = (engine.take_frames(), *engine.time());
+        let state = app.state.ok_or_else(|| ThinError::Other("setup was never run".into()))?;
+        Ok(HeadlessRun { state, frames, time })
+    }
+    /// splits the builder into the parts used to make the engine and the app of callbacks.
+    fn split(self) -> (EngineParts<H>, ClosureApp<'a, H, T, E>) {
+        let app = ClosureApp {
             state: None,
-            window_settings: Some(self.window_settings),
-            update:        &mut self.update,
-            draw:          &mut self.draw,
-            setup:         &mut self.setup,
-            event_handler: &mut self.event_handler,
+            update: self.update,
+            setup:  self.setup,
+            draw:   self.draw,
+            event_handler: self.event_handler,
+            user_event: self.user_event,
+            suspended: self.suspended,
+            resumed: self.resumed
+        };
+        let parts = EngineParts {
+            window_settings: self.window_settings,
+            config_template: self.config_template,
             input_map: self.input_map,
-            settings:  self.settings,
-            frame_start: Instant::now(),
+            settings: self.settings,
+            replay: self.replay,
+            #[cfg(feature = "record")]
+            recording: self.recording
         };
-        ev.run_app(&mut engine)
+        (parts, app)
     }
-    /// this is run whenever a draw request is scheduled
+    /// this is run whenever a draw request is scheduled. when using a fixed timestep a draw is
+    /// requested every frame and `time.alpha` is how far between the previous and next update
+    /// the frame is.
     pub fn with_draw(
         mut self,
-        draw: impl FnMut(&mut InputMap<H>, &Display, &mut Settings, &ActiveEventLoop, &mut Window) + 'a
+        draw: impl FnMut(&mut T, &mut InputMap<H>, &Display, &mut Settings, &ActiveEventLoop, &mut Window, &Time) -> Result<(), ThinError> + 'a
     ) -> Self {
         self.draw = Box::new(draw);
         self
     }
-    /// this is run whenever the window and display are created
-    pub fn with_setup(mut self, setup: impl FnMut(&Display, &mut Window, &ActiveEventLoop) + 'a) -> Self {
-        self.setup = Box::new(setup);
-        self
-    }
-    /// this is run always. if min_duration in settings is set, then it is throttled to said value
+    /// this is run always. if min_duration in settings is set, then it is throttled to said value.
+    /// if a fixed timestep is set, it is instead run in constant steps of that duration, several
+    /// times a frame if needed to catch up.
     pub fn with_update(
         mut self,
-        update: impl FnMut(&mut InputMap<H>, &Display, &mut Settings, &ActiveEventLoop, &mut Window) + 'a
+        update: impl FnMut(&mut T, &mut InputMap<H>, &Display, &mut Settings, &ActiveEventLoop, &mut Window, &Time) -> Result<(), ThinError> + 'a
     ) -> Self {
         self.update = Box::new(update);
         self
     }
     pub fn with_event_handler(
         mut self,
-        event_handler: impl FnMut(Event<()>, &ActiveEventLoop, Option<&(Window, Display)>) + 'a
+        event_handler: impl FnMut(&mut T, Event<()>, &mut Settings, &ActiveEventLoop, Option<&(Window, Display)>) -> Result<(), ThinError> + 'a
     ) -> Self {
         self.event_handler = Box::new(event_handler);
         self
     }
+    /// this is run whenever a user event is sent with an `EventLoopProxy`, such as the one given
+    /// to setup. useful for waking the program from loader or network threads.
+    pub fn with_user_event(
+        mut self,
+        user_event: impl FnMut(&mut T, E, &mut Settings, &ActiveEventLoop, Option<&(Window, Display)>) -> Result<(), ThinError> + 'a
+    ) -> Self {
+        self.user_event = Box::new(user_event);
+        self
+    }
+    /// this is run before the window and display are dropped when the app is suspended. see
+    /// `ThinApp` for what happens to GPU resources.
+    pub fn with_suspended(
+        mut self,
+        suspended: impl FnMut(&mut T, &ActiveEventLoop) -> Result<(), ThinError> + 'a
+    ) -> Self {
+        self.suspended = Box::new(suspended);
+        self
+    }
+    /// this is run whenever the app is resumed with the new window and display, after setup when
+    /// it first starts and after `settings.resources` are rebuilt otherwise.
+    pub fn with_resumed(
+        mut self,
+        resumed: impl FnMut(&mut T, &Display, &mut Window, &ActiveEventLoop, &mut Settings) -> Result<(), ThinError> + 'a
+    ) -> Self {
+        self.resumed = Box::new(resumed);
+        self
+    }
     pub fn with_settings(mut self, settings: Settings) -> Self {
         self.settings = settings;
         self
     }
+    /// replaces input from real devices with a script and runs with a fixed clock. see
+    /// `ThinEngine::with_replay()`
+    pub fn with_replay(mut self, script: InputScript) -> Self {
+        self.replay = Some(script);
+        self
+    }
+    /// records input to a file that can be replayed with `with_replay()`. the file is created
+    /// when the program is built.
+    #[cfg(feature = "record")]
+    pub fn with_recording(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.recording = Some(path.into());
+        self
+    }
+    /// runs update at a fixed rate of `tps` ticks per second. see `Settings::set_fixed_tps()`
+    pub fn with_fixed_tps(mut self, tps: u32) -> Self {
+        self.settings.set_fixed_tps(Some(tps));
+        self
+    }
+    /// sets the window attributes and config template. both are kept so the window is rebuilt
+    /// the same after a suspend.
     pub fn with_window_settings(mut self, window_settings: SimpleWindowBuilder) -> Self {
         self.window_settings = window_settings;
         self
     }
+    /// sets the config template used to pick the display's config, such as the amount of
+    /// samples, replacing the one set with `with_window_settings()`.
+    pub fn with_config_template(mut self, config_template: ConfigTemplateBuilder) -> Self {
+        self.config_template = Some(config_template);
+        self
+    }
 }
@@ -0,0 +1,322 @@
+//! Loads assets from files into shared handles, found in `settings.assets`.
+//!
+//! Loading the same path as the same type twice gives another handle to the asset that is
+//! already loaded. Once every handle to an asset is dropped it is unloaded at the end of the
+//! frame. Assets are kept in a `Resources` registry so they are loaded again from their files
+//! after the display is recreated.
+//!
+//! With `hot_reload` set, which it is by default in debug builds, assets are loaded again when
+//! their files change so shaders can be edited while the game runs. If the new version fails
+//! to load the old one is kept and the error is kept in `take_reload_errors()`.
+//! ```no_run
+//! use thin_engine::{prelude::*, meshes::{MeshBuffers, MeshData}};
+//! struct Graphics {
+//!     house: Resource<MeshBuffers>,
+//!     shader: Resource<Program>
+//! }
+//! let event_loop = EventLoop::new().unwrap();
+//! thin_engine::builder(input_map!()).with_setup(|display, _, _, settings, _| {
+//!     settings.assets.root = "assets".into();
+//!     Ok(Graphics {
+//!         house: settings.assets.load(display, "house.obj")?,
+//!         // loads shaders/lit.vert and shaders/lit.frag
+//!         shader: settings.assets.load(display, "shaders/lit")?
+//!     })
+//! }).with_update(|graphics, _, display, settings, _, _, _| {
+//!     // already loaded so this is the same mesh
+//!     let house: Resource<MeshBuffers> = settings.assets.load(display, "house.obj")?;
+//!     // loaded on the CPU instead, e.g. for collision
+//!     let collision: Resource<MeshData> = settings.assets.load(display, "house.obj")?;
+//!     for (path, error) in settings.assets.take_reload_errors() {
+//!         // e.g. show the shader compile error in game
+//!     }
+//!     Ok(())
+//! }).build(event_loop).unwrap();
+//! ```
+#![allow(clippy::type_complexity)]
+use crate::{Display, ThinError, meshes::{self, MeshData, MeshBuffers}, resources::{Resources, Resource}};
+use glium::Program;
+use std::{
+    any::{Any, TypeId}, cell::RefCell, collections::HashMap, path::{Path, PathBuf}, rc::{Rc, Weak},
+    time::{Duration, Instant, SystemTime}
+};
+
+/// something that can be loaded from a file by `Assets`. `D` is what it is loaded with, only
+/// changed from `Display` to load assets without a GPU.
+pub trait LoadAsset<D = Display>: Sized + 'static {
+    fn load(display: &D, path: &Path) -> Result<Self, ThinError>;
+    /// the files that are watched for hot reloading. defaults to just `path`.
+    fn files(path: &Path) -> Vec<PathBuf> { vec![path.to_path_buf()] }
+}
+fn unsupported(path: &Path) -> ThinError {
+    ThinError::Other(format!("unsupported file type {}", path.display()).into())
+}
+/// loads `name.vert`, `name.frag` and `name.geom` if it exists, ignoring the extension of
+/// `path`.
+impl LoadAsset for Program {
+    fn load(display: &Display, path: &Path) -> Result<Self, ThinError> {
+        let vertex = std::fs::read_to_string(path.with_extension("vert"))?;
+        let fragment = std::fs::read_to_string(path.with_extension("frag"))?;
+        let geometry = std::fs::read_to_string(path.with_extension("geom")).ok();
+        Ok(Program::from_source(display, &vertex, &fragment, geometry.as_deref())?)
+    }
+    fn files(path: &Path) -> Vec<PathBuf> {
+        ["vert", "frag", "geom"].into_iter().map(|i| path.with_extension(i)).collect()
+    }
+}
+/// loads an OBJ file or, with the `gltf` feature, a glTF file as one merged mesh.
+impl<D> LoadAsset<D> for MeshData {
+    fn load(_: &D, path: &Path) -> Result<Self, ThinError> {
+        match path.extension().and_then(|i| i.to_str()).unwrap_or("") {
+            "obj" => Ok(meshes::obj::load(path)?.merged()),
+            #[cfg(feature = "gltf")]
+            "gltf" | "glb" => Ok(meshes::gltf::load(path)?.merged()),
+            _ => Err(unsupported(path))
+        }
+    }
+}
+/// loads the same files as `MeshData` and uploads them.
+impl LoadAsset for MeshBuffers {
+    fn load(display: &Display, path: &Path) -> Result<Self, ThinError> {
+        MeshData::load(display, path)?.upload(display)
+    }
+}
+impl<D> LoadAsset<D> for meshes::obj::Obj {
+    fn load(_: &D, path: &Path) -> Result<Self, ThinError> { meshes::obj::load(path) }
+}
+#[cfg(feature = "gltf")]
+impl<D> LoadAsset<D> for meshes::gltf::Gltf {
+    fn load(_: &D, path: &Path) -> Result<Self, ThinError> { meshes::gltf::load(path) }
+}
+#[cfg(feature = "image")]
+impl LoadAsset for glium::Texture2d {
+    fn load(display: &Display, path: &Path) -> Result<Self, ThinError> {
+        Ok(crate::textures::load(display, path, Default::default())?)
+    }
+}
+#[cfg(feature = "image")]
+impl LoadAsset for glium::texture::SrgbTexture2d {
+    fn load(display: &Display, path: &Path) -> Result<Self, ThinError> {
+        Ok(crate::textures::load(display, path, Default::default())?)
+    }
+}
+impl<D> LoadAsset<D> for String {
+    fn load(_: &D, path: &Path) -> Result<Self, ThinError> { Ok(std::fs::read_to_string(path)?) }
+}
+impl<D> LoadAsset<D> for Vec<u8> {
+    fn load(_: &D, path: &Path) -> Result<Self, ThinError> { Ok(std::fs::read(path)?) }
+}
+/// an asset that is loaded and the files it was loaded from.
+struct Loaded<D> {
+    value: Weak<dyn Any>,
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    /// loads the asset again, replacing it only if it loads successfully.
+    reload: Box<dyn FnMut(&D) -> Result<(), ThinError>>
+}
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|i| i.modified()).ok()
+}
+/// assets loaded from files, shared between everything that loads the same file. found in
+/// `Settings::assets`. `D` is what assets are loaded with, see `LoadAsset`.
+pub struct Assets<D = Display> {
+    /// the folder paths are relative to. defaults to the working directory.
+    pub root: PathBuf,
+    /// how often to check if files have changed and reload them. defaults to every half second
+    /// in debug builds and never in release builds.
+    pub hot_reload: Option<Duration>,
+    resources: Resources<D>,
+    loaded: HashMap<(TypeId, PathBuf, String), Loaded<D>>,
+    last_check: Option<Instant>,
+    reload_errors: Vec<(PathBuf, ThinError)>
+}
+impl<D> Default for Assets<D> {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::new(), hot_reload: cfg!(debug_assertions).then_some(Duration::from_millis(500)),
+            resources: Resources::default(), loaded: HashMap::new(), last_check: None, reload_errors: Vec::new()
+        }
+    }
+}
+impl<D: 'static> Assets<D> {
+    /// loads a file, or returns the asset if it is already loaded as the same type.
+    pub fn load<T: LoadAsset<D>>(&mut self, display: &D, path: impl AsRef<Path>) -> Result<Resource<T>, ThinError> {
+        let path = self.root.join(path);
+        let files = T::files(&path);
+        self.insert(display, path, String::new(), files, T::load)
+    }
+    /// loads a file with a custom function, or returns the asset if it is already loaded as the
+    /// same type and `variant`. useful for types that don't implement `LoadAsset`, such as fonts
+    /// which need a scale, or loading with different settings. `variant` tells apart assets
+    /// loaded from the same file in different ways, the empty variant is shared with `load()`.
+    /// ```no_run
+    /// # use thin_engine::{prelude::*, meshes::{obj, MeshData}};
+    /// # fn load(display: &Display, settings: &mut Settings) -> Result<(), ThinError> {
+    /// let flat: Resource<MeshData> = settings.assets.load_with(display, "rock.obj", "flat", |_, path| {
+    ///     let mut mesh = obj::load(path)?.merged();
+    ///     mesh.flat_normals();
+    ///     Ok(mesh)
+    /// })?;
+    /// # Ok(()) }
+    /// ```
+    pub fn load_with<T: 'static>(
+        &mut self, display: &D, path: impl AsRef<Path>, variant: &str,
+        load: impl Fn(&D, &Path) -> Result<T, ThinError> + 'static
+    ) -> Result<Resource<T>, ThinError> {
+        let path = self.root.join(path);
+        self.insert(display, path.clone(), variant.to_string(), vec![path], load)
+    }
+    fn insert<T: 'static>(
+        &mut self, display: &D, path: PathBuf, variant: String, files: Vec<PathBuf>,
+        load: impl Fn(&D, &Path) -> Result<T, ThinError> + 'static
+    ) -> Result<Resource<T>, ThinError> {
+        let key = (TypeId::of::<T>(), path.clone(), variant);
+        if let Some(value) = self.loaded.get(&key).and_then(|i| i.value.upgrade()).and_then(|i| i.downcast().ok()) {
+            return Ok(Resource(value))
+        }
+        let files = files.into_iter().map(|i| { let time = modified(&i); (i, time) }).collect();
+        let load = Rc::new(load);
+        let resource = {
+            let (load, path) = (load.clone(), path.clone());
+            self.resources.add(display, move |display| load(display, &path))?
+        };
+        let weak: Weak<RefCell<Option<T>>> = Rc::downgrade(&resource.0);
+        let reload = Box::new(move |display: &D| {
+            let Some(value) = weak.upgrade() else { return Ok(()) };
+            let new = load(display, &path)?;
+            *value.borrow_mut() = Some(new);
+            Ok(())
+        });
+        let value = Rc::downgrade(&resource.0) as Weak<dyn Any>;
+        self.loaded.insert(key, Loaded { value, files, reload });
+        Ok(resource)
+    }
+    /// returns true if the file is loaded as `T` by `load()`.
+    pub fn is_loaded<T: 'static>(&self, path: impl AsRef<Path>) -> bool {
+        let key = (TypeId::of::<T>(), self.root.join(path), String::new());
+        self.loaded.get(&key).is_some_and(|i| i.value.strong_count() > 0)
+    }
+    /// the amount of loaded assets.
+    pub fn len(&self) -> usize { self.resources.len() }
+    /// returns true if no assets are loaded.
+    pub fn is_empty(&self) -> bool { self.resources.is_empty() }
+    /// unloads assets that no handles point to. thin engine runs this at the end of every frame.
+    pub fn unload_unused(&mut self) {
+        self.resources.remove_unused();
+        self.loaded.retain(|_, i| i.value.strong_count() > 0);
+    }
+    /// loads assets whose files have changed again. if one fails to load the old version is
+    /// kept and the error is added to `take_reload_errors()`. thin engine runs this
+    /// every `hot_reload` duration.
+    pub fn reload_changed(&mut self, display: &D) {
+        for ((_, path, _), loaded) in &mut self.loaded {
+            let mut changed = false;
+            for (file, time) in &mut loaded.files {
+                let new = modified(file);
+                if new != *time { *time = new; changed = true }
+            }
+            if !changed { continue }
+            if let Err(error) = (loaded.reload)(display) {
+                self.reload_errors.push((path.clone(), error));
+            }
+        }
+    }
+    /// takes the errors from assets that failed to hot reload since this was last called.
+    pub fn take_reload_errors(&mut self) -> Vec<(PathBuf, ThinError)> {
+        std::mem::take(&mut self.reload_errors)
+    }
+    /// runs `reload_changed()` if `hot_reload` is set and enough time has passed.
+    pub(crate) fn hot_reload(&mut self, display: &D) {
+        let Some(interval) = self.hot_reload else { return };
+        if self.last_check.is_some_and(|i| i.elapsed() < interval) { return }
+        self.last_check = Some(Instant::now());
+        self.reload_changed(display);
+    }
+    pub(crate) fn release(&mut self) {
+        self.unload_unused();
+        self.resources.release();
+    }
+    pub(crate) fn recreate(&mut self, display: &D) -> Result<(), ThinError> {
+        self.resources.recreate(display)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    /// assets loaded without a display from a new folder.
+    fn assets(name: &str) -> Assets<()> {
+        let root = std::env::temp_dir().join(format!("thin-engine-{}-{name}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        Assets { root, hot_reload: None, ..Default::default() }
+    }
+    #[test]
+    fn loading_twice_shares_the_asset() {
+        let mut assets = assets("shared");
+        std::fs::write(assets.root.join("a.txt"), "a").unwrap();
+        let first: Resource<String> = assets.load(&(), "a.txt").unwrap();
+        let second: Resource<String> = assets.load(&(), "a.txt").unwrap();
+        assert!(Rc::ptr_eq(&first.0, &second.0));
+        assert_eq!(assets.len(), 1);
+
+        let upper = assets.load_with(&(), "a.txt", "upper", |_, path| {
+            Ok(std::fs::read_to_string(path)?.to_uppercase())
+        }).unwrap();
+        assert!(!Rc::ptr_eq(&first.0, &upper.0));
+        assert_eq!((first.get().as_str(), upper.get().as_str()), ("a", "A"));
+        // the same file as another type is another asset
+        let bytes: Resource<Vec<u8>> = assets.load(&(), "a.txt").unwrap();
+        assert_eq!(*bytes.get(), b"a");
+        assert_eq!(assets.len(), 3);
+        std::fs::remove_dir_all(&assets.root).unwrap();
+    }
+    #[test]
+    fn dropped_assets_are_unloaded() {
+        let mut assets = assets("unload");
+        std::fs::write(assets.root.join("a.txt"), "a").unwrap();
+        let asset: Resource<String> = assets.load(&(), "a.txt").unwrap();
+        assert!(assets.is_loaded::<String>("a.txt"));
+        assert!(!assets.is_loaded::<Vec<u8>>("a.txt"));
+
+        drop(asset);
+        assets.unload_unused();
+        assert!(!assets.is_loaded::<String>("a.txt"));
+        assert!(assets.is_empty());
+        std::fs::remove_dir_all(&assets.root).unwrap();
+    }
+    /// writes a file with a different modified time than it had so the change is always seen.
+    fn rewrite(path: &Path, contents: &str, seconds: u64) {
+        std::fs::write(path, contents).unwrap();
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(seconds);
+        std::fs::File::options().write(true).open(path).unwrap().set_modified(time).unwrap();
+    }
+    #[test]
+    fn changed_files_are_reloaded() {
+        let mut assets = assets("reload");
+        let path = assets.root.join("a.txt");
+        rewrite(&path, "a", 1);
+        let asset: Resource<String> = assets.load(&(), "a.txt").unwrap();
+        rewrite(&path, "b", 2);
+        assets.reload_changed(&());
+        assert_eq!(*asset.get(), "b");
+        assert!(assets.take_reload_errors().is_empty());
+        std::fs::remove_dir_all(&assets.root).unwrap();
+    }
+    #[test]
+    fn failed_reloads_keep_the_old_asset() {
+        let mut assets = assets("failed-reload");
+        let path = assets.root.join("number.txt");
+        rewrite(&path, "1", 1);
+        let number = assets.load_with(&(), "number.txt", "", |_, path| {
+            std::fs::read_to_string(path)?.parse::<u32>().map_err(|i| ThinError::Other(i.into()))
+        }).unwrap();
+        rewrite(&path, "one", 2);
+        assets.reload_changed(&());
+        assert_eq!(*number.get(), 1);
+        // the file hasn't changed again so it isn't retried
+        assets.reload_changed(&());
+        let errors = assets.take_reload_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, path);
+        assert!(assets.take_reload_errors().is_empty());
+        std::fs::remove_dir_all(&assets.root).unwrap();
+    }
+}
@@ -0,0 +1,203 @@
+//! Reads frames and textures back from the GPU as RGBA images and saves them as PNGs, for
+//! screenshots, bug reports and image tests.
+//!
+//! The front buffer holds the last finished frame, so read it after `frame.finish()`. A
+//! `ScreenshotKey` saves a screenshot when an action in the input map is pressed.
+//! ```no_run
+//! use thin_engine::{prelude::*, capture::ScreenshotKey};
+//! #[derive(Hash, PartialEq, Eq, Clone, Copy)]
+//! enum Action { Screenshot }
+//! let event_loop = EventLoop::new().unwrap();
+//! let input = input_map!((Action::Screenshot, KeyCode::F12));
+//! thin_engine::builder(input).with_setup(|_, _, _, _, _| {
+//!     Ok(ScreenshotKey::new(Action::Screenshot, "screenshots"))
+//! }).with_update(|screenshots, input, _, _, _, _, _| {
+//!     screenshots.update(input);
+//!     Ok(())
+//! }).with_draw(|screenshots, _, display, _, _, window, _| {
+//!     let mut frame = display.draw();
+//!     frame.clear_color(0.2, 0.4, 0.8, 1.0);
+//!     window.pre_present_notify();
+//!     frame.finish()?;
+//!     if let Some(path) = screenshots.save_if_requested(display)? {
+//!         println!("saved {}", path.display());
+//!     }
+//!     Ok(())
+//! }).build(event_loop).unwrap();
+//! ```
+use crate::{ThinError, ResizableTexture2d};
+use glium::{Texture2d, backend::Facade, texture::{RawImage2d, SrgbTexture2d}};
+use winit_input_map::InputMap;
+use std::{hash::Hash, fs::File, io::BufWriter, path::{Path, PathBuf}, time::SystemTime};
+
+/// an RGBA image with 8 bits per channel, stored from the top row down.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>
+}
+impl Image {
+    /// creates an image filled with one colour.
+    pub fn new(width: u32, height: u32, colour: [u8; 4]) -> Self {
+        let pixels = colour.repeat((width * height) as usize);
+        Self { width, height, pixels }
+    }
+    /// reads a texture back from the GPU.
+    pub fn from_texture(texture: &Texture2d) -> Self {
+        let raw: RawImage2d<u8> = texture.read();
+        Self::from_bottom_up(raw.width, raw.height, raw.data.into_owned())
+    }
+    /// flips OpenGL's bottom up rows.
+    pub(crate) fn from_bottom_up(width: u32, height: u32, pixels: Vec<u8>) -> Self {
+        let pixels = pixels.chunks_exact(width as usize * 4).rev().flatten().copied().collect();
+        Self { width, height, pixels }
+    }
+    /// the colour of the pixel at x, y with 0, 0 being the top left.
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = (y * self.width + x) as usize * 4;
+        [self.pixels[i], self.pixels[i + 1], self.pixels[i + 2], self.pixels[i + 3]]
+    }
+    pub fn set_pixel(&mut self, x: u32, y: u32, colour: [u8; 4]) {
+        let i = (y * self.width + x) as usize * 4;
+        self.pixels[i..i + 4].copy_from_slice(&colour);
+    }
+    /// the image with OpenGL's bottom up rows.
+    fn raw(&self) -> RawImage2d<'static, u8> {
+        let rows: Vec<u8> = self.pixels.chunks_exact(self.width as usize * 4).rev().flatten().copied().collect();
+        RawImage2d::from_raw_rgba(rows, (self.width, self.height))
+    }
+    /// uploads the image as linear data with mipmaps, e.g. for normal maps.
+    pub fn to_texture(&self, display: &impl Facade) -> Result<Texture2d, ThinError> {
+        Ok(Texture2d::new(display, self.raw())?)
+    }
+    /// uploads the image as srgb with mipmaps, for colour textures.
+    pub fn to_srgb_texture(&self, display: &impl Facade) -> Result<SrgbTexture2d, ThinError> {
+        Ok(SrgbTexture2d::new(display, self.raw())?)
+    }
+    /// saves the image as a PNG, creating the parent folders if needed.
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<(), ThinError> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() { std::fs::create_dir_all(parent)? }
+        let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        writer.finish()?;
+        Ok(())
+    }
+    /// loads a PNG, converting it to 8 bit RGBA.
+    pub fn load_png(path: impl AsRef<Path>) -> Result<Self, ThinError> {
+        let mut decoder = png::Decoder::new(File::open(path)?);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+        buffer.truncate(info.buffer_size());
+        let pixels = match info.color_type {
+            png::ColorType::Rgba => buffer,
+            png::ColorType::Rgb => buffer.chunks_exact(3).flat_map(|i| [i[0], i[1], i[2], 255]).collect(),
+            png::ColorType::GrayscaleAlpha => buffer.chunks_exact(2).flat_map(|i| [i[0], i[0], i[0], i[1]]).collect(),
+            png::ColorType::Grayscale => buffer.iter().flat_map(|&i| [i, i, i, 255]).collect(),
+            // indexed images are expanded by the transformations
+            png::ColorType::Indexed => unreachable!()
+        };
+        Ok(Self { width: info.width, height: info.height, pixels })
+    }
+}
+/// reads the last finished frame. call this after `frame.finish()`.
+pub fn read_framebuffer(display: &impl Facade) -> Result<Image, ThinError> {
+    let raw: RawImage2d<u8> = display.get_context().read_front_buffer()?;
+    Ok(Image::from_bottom_up(raw.width, raw.height, raw.data.into_owned()))
+}
+/// reads a texture, such as the one drawn to for post processing.
+pub fn read_texture(texture: &ResizableTexture2d) -> Image {
+    Image::from_texture(texture.texture())
+}
+/// reads the last finished frame and saves it as a PNG.
+pub fn screenshot(display: &impl Facade, path: impl AsRef<Path>) -> Result<(), ThinError> {
+    read_framebuffer(display)?.save_png(path)
+}
+/// saves a screenshot to a folder when an action is pressed. call `update()` in update and
+/// `save_if_requested()` in draw after the frame is finished.
+pub struct ScreenshotKey<H: Hash + PartialEq + Eq + Clone + Copy> {
+    pub action: H,
+    pub directory: PathBuf,
+    requested: bool,
+    count: u32
+}
+impl<H: Hash + PartialEq + Eq + Clone + Copy> ScreenshotKey<H> {
+    pub fn new(action: H, directory: impl Into<PathBuf>) -> Self {
+        Self { action, directory: directory.into(), requested: false, count: 0 }
+    }
+    /// checks if the action was pressed.
+    pub fn update(&mut self, input: &InputMap<H>) {
+        if input.pressed(self.action) { self.requested = true }
+    }
+    /// takes a screenshot next time `save_if_requested()` is called.
+    pub fn request(&mut self) { self.requested = true }
+    /// saves the last finished frame if the action was pressed, returning the path it was saved to.
+    pub fn save_if_requested(&mut self, display: &impl Facade) -> Result<Option<PathBuf>, ThinError> {
+        if !self.requested { return Ok(None) }
+        self.requested = false;
+
+        let secs = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|i| i.as_secs()).unwrap_or(0);
+        let path = self.directory.join(format!("screenshot-{secs}-{}.png", self.count));
+        self.count += 1;
+        screenshot(display, &path)?;
+        Ok(Some(path))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("thin-engine-{}-{name}.png", std::process::id()))
+    }
+    fn gradient() -> Image {
+        let mut image = Image::new(3, 2, [0, 0, 0, 255]);
+        for (x, y) in (0..3).flat_map(|x| (0..2).map(move |y| (x, y))) {
+            image.set_pixel(x, y, [x as u8 * 100, y as u8 * 200, 7, 128 + x as u8]);
+        }
+        image
+    }
+    #[test]
+    fn pixels_start_from_the_top_left() {
+        let image = gradient();
+        assert_eq!(image.pixels.len(), 3 * 2 * 4);
+        assert_eq!(image.pixel(0, 0), [0, 0, 7, 128]);
+        assert_eq!(image.pixel(2, 1), [200, 200, 7, 130]);
+        assert_eq!(&image.pixels[4..8], &[100, 0, 7, 129]);
+        assert_eq!(Image::new(2, 2, [1, 2, 3, 4]).pixel(1, 1), [1, 2, 3, 4]);
+    }
+    #[test]
+    fn bottom_up_rows_are_flipped() {
+        let image = gradient();
+        let rows: Vec<u8> = image.pixels.chunks_exact(3 * 4).rev().flatten().copied().collect();
+        assert_eq!(Image::from_bottom_up(3, 2, rows), image);
+    }
+    #[test]
+    fn png_round_trip() {
+        let path = temp_path("round-trip");
+        let image = gradient();
+        image.save_png(&path).unwrap();
+        assert_eq!(Image::load_png(&path).unwrap(), image);
+        std::fs::remove_file(path).unwrap();
+    }
+    #[test]
+    fn load_converts_to_rgba() {
+        let path = temp_path("grey");
+        let mut encoder = png::Encoder::new(BufWriter::new(File::create(&path).unwrap()), 2, 1);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[10, 250]).unwrap();
+        writer.finish().unwrap();
+        let image = Image::load_png(&path).unwrap();
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.pixels, [10, 10, 10, 255, 250, 250, 250, 255]);
+        std::fs::remove_file(path).unwrap();
+        assert!(Image::load_png(temp_path("missing")).is_err());
+    }
+}
@@ -0,0 +1,201 @@
+//! Golden image tests for guarding shaders against regressions. A scene is drawn with a
+//! headless context and compared against a reference PNG. If too many pixels differ the test
+//! fails and the output and a diff image are written next to the reference.
+//!
+//! A missing reference is created from the output and the test fails so it can be checked
+//! before it is committed. Set `THIN_ENGINE_UPDATE_GOLDEN=1` to overwrite every reference.
+//! ```no_run
+//! use thin_engine::{prelude::*, golden::{self, Golden}, meshes::screen};
+//! // in a test, see tests/golden.rs
+//! let image = golden::render(64, 64, |display, frame| {
+//!     let (indices, vertices, uvs) = mesh!(
+//!         display, &screen::INDICES, &screen::VERTICES, &screen::UVS
+//!     )?;
+//!     let checker = golden::checkerboard(64, 64, 8).to_texture(display)?;
+//!     let fxaa = shaders::fxaa_shader(display)?;
+//!     frame.draw(
+//!         (&vertices, &uvs), &indices, &fxaa,
+//!         &shaders::fxaa_uniforms(&checker), &DrawParameters::default()
+//!     )?;
+//!     Ok(())
+//! }).unwrap();
+//! Golden::new("tests/golden/fxaa.png").with_tolerance(2).assert(&image);
+//! ```
+use crate::{ThinError, capture::Image, headless::Headless};
+use glium::{Texture2d, Surface, framebuffer::SimpleFrameBuffer, texture::DepthTexture2d};
+use std::path::{Path, PathBuf};
+
+/// draws one frame with a new headless context and reads it back. the frame is cleared to
+/// transparent black and has a depth buffer.
+pub fn render(
+    width: u32, height: u32,
+    draw: impl FnOnce(&Headless, &mut SimpleFrameBuffer) -> Result<(), ThinError>
+) -> Result<Image, ThinError> {
+    let headless = Headless::new((width, height))?;
+    let colour = Texture2d::empty(&headless, width, height)?;
+    let depth = DepthTexture2d::empty(&headless, width, height)?;
+    let mut frame = SimpleFrameBuffer::with_depth_buffer(&headless, &colour, &depth)?;
+    frame.clear_color_and_depth((0.0, 0.0, 0.0, 0.0), 1.0);
+    draw(&headless, &mut frame)?;
+    Ok(Image::from_texture(&colour))
+}
+/// a black and white checkerboard, a useful input for post processing shaders.
+pub fn checkerboard(width: u32, height: u32, square: u32) -> Image {
+    let mut image = Image::new(width, height, [0, 0, 0, 255]);
+    let square = square.max(1);
+    for y in 0..height { for x in 0..width {
+        if (x / square + y / square).is_multiple_of(2) { image.set_pixel(x, y, [255; 4]) }
+    } }
+    image
+}
+/// the difference between two images of the same size.
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    /// the amount of pixels with a channel that differs by more than the tolerance.
+    pub differing: usize,
+    /// the largest difference of any channel.
+    pub max_difference: u8,
+    /// the expected image dimmed with differing pixels in red.
+    pub diff: Image
+}
+/// compares two images of the same size. a pixel differs if any channel differs by more than
+/// `tolerance`.
+pub fn compare(expected: &Image, actual: &Image, tolerance: u8) -> Comparison {
+    let mut diff = Image::new(expected.width, expected.height, [0, 0, 0, 255]);
+    let mut differing = 0;
+    let mut max_difference = 0;
+    for (i, (a, b)) in expected.pixels.chunks_exact(4).zip(actual.pixels.chunks_exact(4)).enumerate() {
+        let difference = a.iter().zip(b).map(|(a, b)| a.abs_diff(*b)).max().unwrap_or(0);
+        max_difference = max_difference.max(difference);
+        let colour = if difference > tolerance {
+            differing += 1;
+            [255, 0, 0, 255]
+        } else {
+            let grey = ((a[0] as u32 + a[1] as u32 + a[2] as u32) / 9) as u8;
+            [grey, grey, grey, 255]
+        };
+        diff.pixels[i * 4..i * 4 + 4].copy_from_slice(&colour);
+    }
+    Comparison { differing, max_difference, diff }
+}
+/// why a golden image test failed.
+#[derive(Debug)]
+pub enum GoldenError {
+    /// the reference was missing and has been created from the output.
+    MissingReference(PathBuf),
+    SizeMismatch { expected: (u32, u32), actual: (u32, u32) },
+    /// too many pixels differed. the diff image was saved to `diff`.
+    Mismatch { differing: usize, max_difference: u8, diff: PathBuf },
+    Thin(ThinError)
+}
+impl std::fmt::Display for GoldenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingReference(path) => write!(f, "no reference image, created {}. check it and run again", path.display()),
+            Self::SizeMismatch { expected, actual } => write!(f, "expected a {expected:?} image but got {actual:?}"),
+            Self::Mismatch { differing, max_difference, diff } => write!(
+                f, "{differing} pixels differed by up to {max_difference}, see {}", diff.display()
+            ),
+            Self::Thin(e) => e.fmt(f)
+        }
+    }
+}
+impl std::error::Error for GoldenError {}
+impl From<ThinError> for GoldenError {
+    fn from(err: ThinError) -> Self { Self::Thin(err) }
+}
+/// a reference image to compare output against.
+#[derive(Debug, Clone)]
+pub struct Golden {
+    pub reference: PathBuf,
+    /// how much a channel can differ before the pixel counts as different. defaults to 0.
+    pub tolerance: u8,
+    /// how many pixels can differ before the test fails. defaults to 0.
+    pub max_differing: usize
+}
+impl Golden {
+    pub fn new(reference: impl Into<PathBuf>) -> Self {
+        Self { reference: reference.into(), tolerance: 0, max_differing: 0 }
+    }
+    pub fn with_tolerance(mut self, tolerance: u8) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+    pub fn with_max_differing(mut self, pixels: usize) -> Self {
+        self.max_differing = pixels;
+        self
+    }
+    /// where the output is saved on failure, `name.actual.png` next to the reference.
+    pub fn actual_path(&self) -> PathBuf { self.sibling("actual") }
+    /// where the diff image is saved on failure, `name.diff.png` next to the reference.
+    pub fn diff_path(&self) -> PathBuf { self.sibling("diff") }
+    fn sibling(&self, kind: &str) -> PathBuf {
+        let stem = self.reference.file_stem().unwrap_or_default().to_string_lossy();
+        self.reference.with_file_name(format!("{stem}.{kind}.png"))
+    }
+    /// compares the image against the reference, saving the output and a diff on failure.
+    pub fn check(&self, image: &Image) -> Result<(), GoldenError> {
+        let update = std::env::var("THIN_ENGINE_UPDATE_GOLDEN").is_ok_and(|i| i == "1");
+        if update || !Path::new(&self.reference).exists() {
+            image.save_png(&self.reference)?;
+            return if update { Ok(()) } else { Err(GoldenError::MissingReference(self.reference.clone())) }
+        }
+        let expected = Image::load_png(&self.reference)?;
+        if (expected.width, expected.height) != (image.width, image.height) {
+            image.save_png(self.actual_path())?;
+            return Err(GoldenError::SizeMismatch {
+                expected: (expected.width, expected.height), actual: (image.width, image.height)
+            })
+        }
+        let comparison = compare(&expected, image, self.tolerance);
+        if comparison.differing > self.max_differing {
+            image.save_png(self.actual_path())?;
+            comparison.diff.save_png(self.diff_path())?;
+            return Err(GoldenError::Mismatch {
+                differing: comparison.differing, max_difference: comparison.max_difference,
+                diff: self.diff_path()
+            })
+        }
+        Ok(())
+    }
+    /// like `check()` but panics on failure, for use in tests.
+    #[track_caller]
+    pub fn assert(&self, image: &Image) {
+        if let Err(e) = self.check(image) { panic!("golden image {}: {e}", self.reference.display()) }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn compare_tolerance() {
+        let expected = Image::new(2, 2, [100, 100, 100, 255]);
+        let mut actual = expected.clone();
+        actual.set_pixel(0, 0, [102, 100, 100, 255]);
+        actual.set_pixel(1, 1, [100, 100, 110, 255]);
+
+        let comparison = compare(&expected, &actual, 2);
+        assert_eq!(comparison.differing, 1);
+        assert_eq!(comparison.max_difference, 10);
+        assert_eq!(compare(&expected, &actual, 10).differing, 0);
+        assert_eq!(compare(&expected, &actual, 1).differing, 2);
+    }
+    #[test]
+    fn compare_diff_image() {
+        let expected = Image::new(2, 1, [90, 90, 90, 255]);
+        let mut actual = expected.clone();
+        actual.set_pixel(1, 0, [0, 0, 0, 255]);
+
+        let diff = compare(&expected, &actual, 0).diff;
+        // matching pixels are the expected image dimmed, differing ones are red
+        assert_eq!(diff.pixel(0, 0), [30, 30, 30, 255]);
+        assert_eq!(diff.pixel(1, 0), [255, 0, 0, 255]);
+    }
+    #[test]
+    fn checkerboard_squares() {
+        let image = checkerboard(4, 4, 2);
+        assert_eq!(image.pixel(0, 0), [255; 4]);
+        assert_eq!(image.pixel(2, 0), [0, 0, 0, 255]);
+        assert_eq!(image.pixel(2, 2), [255; 4]);
+    }
+}
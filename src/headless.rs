@@ -0,0 +1,278 @@
+//! Runs a program without a window for automated tests. An EGL context is made without a
+//! surface so it works on machines with no display, using a software renderer such as mesa's
+//! llvmpipe if there is no GPU. Frames are drawn to a texture, the clock is simulated and input
+//! is scripted so runs are deterministic.
+//!
+//! The callbacks recieve a `Headless` facade instead of a `Display` and window. To share code
+//! with the windowed program write functions that take `&impl Facade` and `&mut impl Surface`.
+//! An existing `ThinBuilder` program or `ThinApp` can be run unchanged with
+//! `ThinBuilder::build_headless()` and `build_app_headless()`, which use the same simulated clock,
+//! scripts and captures but need a display for their window, such as a virtual one from
+//! `xvfb-run`.
+//! ```no_run
+//! use thin_engine::{prelude::*, headless::*};
+//! #[derive(Hash, PartialEq, Eq, Clone, Copy)]
+//! enum Action { Jump }
+//! let input = input_map!((Action::Jump, KeyCode::Space));
+//! let run = thin_engine::headless::builder(input)
+//!     .with_size(64, 64)
+//!     .with_frames(30)
+//!     .with_script(InputScript::new().tap(10, KeyCode::Space))
+//!     .with_setup(|_, _| Ok(0))
+//!     .with_update(|jumps, input, _, _, _| {
+//!         if input.pressed(Action::Jump) { *jumps += 1 }
+//!         Ok(())
+//!     })
+//!     .with_draw(|_, frame, _, _, _, _| {
+//!         frame.clear_color(1.0, 0.0, 0.0, 1.0);
+//!         Ok(())
+//!     })
+//!     .run().unwrap();
+//! assert_eq!(run.state, 1);
+//! assert_eq!(run.last_frame().unwrap().pixel(0, 0), [255, 0, 0, 255]);
+//! ```
+#![allow(clippy::type_complexity)]
+use crate::{Settings, Time, ThinError};
+pub use crate::{capture::Image, script::{InputScript, ScriptedInput}};
+use crate::script::ScriptedInputMap;
+use glium::{
+    Texture2d, framebuffer::SimpleFrameBuffer, texture::DepthTexture2d,
+    backend::{Backend, Context, Facade}, debug::DebugCallbackBehavior, SwapBuffersError,
+    glutin::{
+        api::egl::{device::Device, display::Display as EglDisplay, context::PossiblyCurrentContext},
+        config::{ConfigTemplateBuilder, ConfigSurfaceTypes},
+        context::ContextAttributesBuilder, display::GetGlDisplay,
+        prelude::*
+    }
+};
+use winit_input_map::InputMap;
+use std::{hash::Hash, rc::Rc, ops::Deref, ffi::{CString, c_void}, time::Duration};
+
+/// an OpenGL context with no surface. implements `Facade` so it can be used anywhere a
+/// `Display` can be used to make buffers, programs and textures.
+pub struct Headless {
+    context: Rc<Context>
+}
+impl Headless {
+    /// makes a context on the first EGL device that supports it. `size` is what
+    /// `get_framebuffer_dimensions()` returns and the size of the frames drawn by a headless run.
+    pub fn new(size: (u32, u32)) -> Result<Self, ThinError> {
+        let mut last_err = None;
+        for device in Device::query_devices()? {
+            match EglBackend::new(&device, size) {
+                Ok(backend) => {
+                    let context = unsafe { Context::new(backend, true, DebugCallbackBehavior::Ignore)? };
+                    return Ok(Self { context })
+                },
+                Err(err) => last_err = Some(err)
+            }
+        }
+        Err(last_err.unwrap_or(ThinError::Other("no EGL devices were found".into())))
+    }
+}
+impl Facade for Headless {
+    fn get_context(&self) -> &Rc<Context> { &self.context }
+}
+impl Deref for Headless {
+    type Target = Context;
+    fn deref(&self) -> &Context { &self.context }
+}
+struct EglBackend {
+    context: PossiblyCurrentContext,
+    size: (u32, u32)
+}
+impl EglBackend {
+    fn new(device: &Device, size: (u32, u32)) -> Result<Self, ThinError> {
+        let display = unsafe { EglDisplay::with_device(device, None)? };
+        let template = ConfigTemplateBuilder::new()
+            .with_surface_type(ConfigSurfaceTypes::empty())
+            .build();
+        let config = unsafe { display.find_configs(template)? }.next()
+            .ok_or_else(|| ThinError::Other("no EGL config was found".into()))?;
+        let attributes = ContextAttributesBuilder::new().build(None);
+        let context = unsafe { display.create_context(&config, &attributes)? }
+            .make_current_surfaceless()?;
+        Ok(Self { context, size })
+    }
+}
+unsafe impl Backend for EglBackend {
+    fn swap_buffers(&self) -> Result<(), SwapBuffersError> { Ok(()) }
+    unsafe fn get_proc_address(&self, symbol: &str) -> *const c_void {
+        let symbol = CString::new(symbol).unwrap();
+        self.context.display().get_proc_address(&symbol)
+    }
+    fn get_framebuffer_dimensions(&self) -> (u32, u32) { self.size }
+    fn resize(&self, _new_size: (u32, u32)) {}
+    fn is_current(&self) -> bool { self.context.is_current() }
+    unsafe fn make_current(&self) {
+        self.context.make_current_surfaceless().expect("failed to make the headless context current")
+    }
+}
+/// which frames a headless run reads back from the GPU.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Capture {
+    /// every frame, uses a lot of memory for long runs.
+    All,
+    /// only the final frame.
+    #[default]
+    Last,
+    /// every nth frame, starting with the first.
+    Every(u64),
+    /// the listed frames.
+    Frames(Vec<u64>),
+    None
+}
+impl Capture {
+    pub(crate) fn includes(&self, frame: u64, frames: u64) -> bool {
+        match self {
+            Capture::All => true,
+            Capture::Last => frame + 1 == frames,
+            Capture::Every(n) => frame.is_multiple_of((*n).max(1)),
+            Capture::Frames(list) => list.contains(&frame),
+            Capture::None => false
+        }
+    }
+}
+/// the result of a headless run.
+pub struct HeadlessRun<T> {
+    /// the state returned by setup after the last frame.
+    pub state: T,
+    /// the captured frames and their frame numbers.
+    pub frames: Vec<(u64, Image)>,
+    /// the time data of the last frame.
+    pub time: Time
+}
+impl<T> HeadlessRun<T> {
+    /// the last captured frame.
+    pub fn last_frame(&self) -> Option<&Image> {
+        self.frames.last().map(|(_, image)| image)
+    }
+    /// the captured image of `frame`, if it was captured.
+    pub fn frame(&self, frame: u64) -> Option<&Image> {
+        self.frames.iter().find(|(i, _)| *i == frame).map(|(_, image)| image)
+    }
+}
+/// creates a `HeadlessBuilder`
+pub fn builder<'a, H: Hash + PartialEq + Eq + Clone + Copy>(input_map: InputMap<H>) -> HeadlessBuilder<'a, H> {
+    HeadlessBuilder::new(input_map)
+}
+/// builds and runs a program without a window for a fixed number of frames. each frame runs
+/// the scripted inputs, update and then draw.
+pub struct HeadlessBuilder<'a, H: Hash + PartialEq + Eq + Clone + Copy, T = ()> {
+    input_map: InputMap<H>,
+    settings: Settings,
+    size: (u32, u32),
+    frames: u64,
+    timestep: Duration,
+    script: InputScript,
+    capture: Capture,
+    setup: Box<dyn FnMut(&Headless, &mut Settings) -> Result<T, ThinError> + 'a>,
+    update: Box<dyn FnMut(&mut T, &mut InputMap<H>, &Headless, &mut Settings, &Time) -> Result<(), ThinError> + 'a>,
+    draw: Box<dyn FnMut(&mut T, &mut SimpleFrameBuffer, &mut InputMap<H>, &Headless, &mut Settings, &Time) -> Result<(), ThinError> + 'a>
+}
+impl<'a, H: Hash + PartialEq + Eq + Clone + Copy> HeadlessBuilder<'a, H> {
+    pub fn new(input_map: InputMap<H>) -> Self {
+        Self {
+            input_map,
+            // gamepads aren't used without a window
+            settings: Settings::new(None, None),
+            size: (800, 480),
+            frames: 1,
+            timestep: Duration::from_secs_f64(1.0 / 60.0),
+            script: InputScript::new(),
+            capture: Capture::Last,
+            setup:  Box::new(|_, _|                Ok(())),
+            update: Box::new(|_, _, _, _, _|       Ok(())),
+            draw:   Box::new(|_, _, _, _, _, _|    Ok(()))
+        }
+    }
+    /// this is run once before the first frame. the returned state is passed to the other
+    /// callbacks and returned by `run()`. callbacks that were set before this ignore the state.
+    pub fn with_setup<T: 'a>(
+        self,
+        setup: impl FnMut(&Headless, &mut Settings) -> Result<T, ThinError> + 'a
+    ) -> HeadlessBuilder<'a, H, T> where H: 'a {
+        let Self { input_map, settings, size, frames, timestep, script, capture, mut update, mut draw, .. } = self;
+        HeadlessBuilder {
+            input_map, settings, size, frames, timestep, script, capture,
+            setup: Box::new(setup),
+            update: Box::new(move |_, i, d, s, t| update(&mut (), i, d, s, t)),
+            draw:   Box::new(move |_, f, i, d, s, t| draw(&mut (), f, i, d, s, t))
+        }
+    }
+}
+impl<'a, H: Hash + PartialEq + Eq + Clone + Copy, T> HeadlessBuilder<'a, H, T> {
+    /// run once a frame with the simulated time.
+    pub fn with_update(
+        mut self,
+        update: impl FnMut(&mut T, &mut InputMap<H>, &Headless, &mut Settings, &Time) -> Result<(), ThinError> + 'a
+    ) -> Self {
+        self.update = Box::new(update);
+        self
+    }
+    /// run once a frame after update, drawing onto a texture the size of the run.
+    pub fn with_draw(
+        mut self,
+        draw: impl FnMut(&mut T, &mut SimpleFrameBuffer, &mut InputMap<H>, &Headless, &mut Settings, &Time) -> Result<(), ThinError> + 'a
+    ) -> Self {
+        self.draw = Box::new(draw);
+        self
+    }
+    pub fn with_settings(mut self, settings: Settings) -> Self {
+        self.settings = settings;
+        self
+    }
+    /// the size of the frames in pixels. defaults to 800 by 480.
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.size = (width, height);
+        self
+    }
+    /// the number of frames to run. defaults to 1.
+    pub fn with_frames(mut self, frames: u64) -> Self {
+        self.frames = frames;
+        self
+    }
+    /// the simulated time between frames. defaults to 1/60th of a second. the fixed timestep in
+    /// settings is used instead if it is set.
+    pub fn with_timestep(mut self, timestep: Duration) -> Self {
+        self.timestep = timestep;
+        self
+    }
+    pub fn with_script(mut self, script: InputScript) -> Self {
+        self.script = script;
+        self
+    }
+    /// which frames are read back as images. defaults to the last frame.
+    pub fn with_capture(mut self, capture: Capture) -> Self {
+        self.capture = capture;
+        self
+    }
+    /// runs the program, returning the final state and the captured frames or the first error.
+    pub fn run(self) -> Result<HeadlessRun<T>, ThinError> {
+        let Self { mut input_map, mut settings, size, frames, timestep, script, capture, mut setup, mut update, mut draw } = self;
+        let headless = Headless::new(size)?;
+        let colour = Texture2d::empty(&headless, size.0, size.1)?;
+        let depth = DepthTexture2d::empty(&headless, size.0, size.1)?;
+        let scripted = ScriptedInputMap::new(&mut input_map);
+
+        let mut state = setup(&headless, &mut settings)?;
+        let mut time = Time::default();
+        let mut captured = Vec::new();
+        for frame in 0..frames {
+            for input in script.inputs_at(frame) { scripted.send(&mut input_map, input) }
+
+            let step = settings.fixed_timestep.unwrap_or(timestep);
+            time.step(step, settings.time_scale);
+            time.measure_fps(step);
+            update(&mut state, &mut input_map, &headless, &mut settings, &time)?;
+
+            let mut target = SimpleFrameBuffer::with_depth_buffer(&headless, &colour, &depth)?;
+            draw(&mut state, &mut target, &mut input_map, &headless, &mut settings, &time)?;
+            if capture.includes(frame, frames) { captured.push((frame, Image::from_texture(&colour))) }
+
+            time.frame += 1;
+            input_map.init();
+        }
+        Ok(HeadlessRun { state, frames: captured, time })
+    }
+}
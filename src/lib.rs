@@ -3,7 +3,6 @@
 //! support and low boilerplate despite having lots of control.
 //! ```
 //! use thin_engine::{prelude::*, meshes::screen};
-//! use std::{cell::RefCell, rc::Rc};
 //! use Action::*;
 //!
 //! #[derive(Hash, PartialEq, Eq, Clone, Copy)]
@@ -28,9 +27,6 @@
 //!     box_vertices: VertexBuffer<Vertex>,
 //!     box_shader: Program
 //! }
-//! let graphics: Rc<RefCell<Option<Graphics>>> = Rc::default();
-//! let graphics_setup = graphics.clone();
-//!
 //! let mut player_pos = Vec2::ZERO;
 //! let mut player_gravity = 0.0;
 //! let mut player_can_jump = true;
@@ -39,11 +35,10 @@
 //! let camera = Mat4::from_scale(Vec3::splat(10.0)).inverse();
 //! 
 //! let settings = Settings::from_fps(60); // target of 60 fps
-//! let mut frame_start = Instant::now();
-//! thin_engine::builder(input).with_setup(move |display, window, _| {
+//! thin_engine::builder(input).with_setup(move |display, window, _, _, _| {
 //!     let (box_indices, box_vertices) = mesh!(
 //!         display, &screen::INDICES, &screen::VERTICES
-//!     ).unwrap();
+//!     )?;
 //!     let box_shader = Program::from_source(
 //!         display,
 //!         "#version 140
@@ -61,14 +56,11 @@
 //!         void main() {
 //!             colour = vec4(1.0, 0.0, 0.0, 1.0);
 //!         }", None
-//!     ).unwrap();
-//!     graphics_setup.replace(Some(Graphics {
-//!         box_vertices, box_indices, box_shader
-//!     }));
-//! }).with_update(move |input, display, _settings, target, window| {
+//!     )?;
+//!     Ok(Graphics { box_vertices, box_indices, box_shader })
+//! }).with_update(move |graphics, input, display, _settings, target, window, time| {
 //!     // gets time between frames
-//!     let delta_time = frame_start.elapsed().as_secs_f32();
-//!     frame_start = Instant::now();
+//!     let delta_time = time.delta_secs();
 //!     
 //!     if input.pressed(Exit) { target.exit() }
 //!
@@ -85,10 +77,9 @@
 //!         player_can_jump = false;
 //!     }
 //!
-//!     let graphics = graphics.borrow();
 //!     let Graphics {
 //!         box_vertices, box_indices, box_shader
-//!     } = graphics.as_ref().unwrap();
+//!     } = &*graphics;
 //!     // set up frame
 //!     let mut frame = display.draw();
 //!     let perspective = Mat4::perspective_2d(frame.get_dimensions());
@@ -101,9 +92,10 @@
 //!             perspective: perspective, camera: camera,
 //!             model: Mat4::from_pos(player_pos.extend(0.0)),
 //!         }, &DrawParameters::default()
-//!     );
+//!     )?;
 //!     window.pre_present_notify();
-//!     frame.finish().unwrap();
+//!     frame.finish()?;
+//!     Ok(())
 //! }).with_settings(Settings::from_fps(60))
 //!     .build(event_loop).unwrap();
 //! ```
@@ -117,17 +109,36 @@ pub use glium_types;
 pub use winit;
 pub use winit_input_map as input_map;
 use std::time::Duration;
+use winit::window::{Window, WindowId};
 
 /// Run time settings for thin engine including gamepad settings (through gilrs) and fps settings.
 /// when running `default()` the gamepads may fail to initialise and the program will continue
 /// running after printing the error. If this is undesirable use `with_gamepads()` instead.
 pub struct Settings {
     pub gamepads: Option<Gilrs>,
-    pub min_frame_duration: Option<Duration>
+    pub min_frame_duration: Option<Duration>,
+    /// when set, update is run in fixed steps of this duration instead of once per frame and
+    /// draw is requested every frame with an interpolation alpha between steps.
+    pub fixed_timestep: Option<Duration>,
+    /// the most fixed steps that can be run in one frame. if the game falls further behind
+    /// than this the extra time is dropped so that it slows down instead of locking up.
+    pub max_fixed_steps: u32,
+    /// multiplies the delta time given to update and draw. set to 0 to pause the game while
+    /// still recieving updates.
+    pub time_scale: f32,
+    /// GPU resources that are rebuilt when the display is recreated after a suspend.
+    pub resources: resources::Resources,
+    /// assets loaded from files, shared by handles and unloaded when they are unused.
+    pub assets: assets::Assets,
+    pub(crate) new_windows: Vec<(Window, Display)>,
+    pub(crate) closed_windows: Vec<WindowId>
 }
 impl Settings {
     pub fn new(gamepads: Option<Gilrs>, min_frame_duration: Option<Duration>) -> Self {
-        Self { gamepads, min_frame_duration }
+        Self {
+            gamepads, min_frame_duration, fixed_timestep: None, max_fixed_steps: 8, time_scale: 1.0,
+            resources: resources::Resources::default(), assets: assets::Assets::default(), new_windows: Vec::new(), closed_windows: Vec::new()
+        }
     }
     /// creates settings with the minimum frame duration set to 1 / fps.
     pub fn from_fps(fps: u32) -> Self {
@@ -137,6 +148,7 @@ impl Settings {
         Self::new(gamepads, min_frame_duration)
     }
     /// guarantees gamepads will be set instead of printing an error and moving on.
+    #[allow(clippy::result_large_err)]
     pub fn with_gamepads() -> Result<Self, gilrs::Error> {
         let gilrs = Gilrs::new()?;
         Ok(Self::new(Some(gilrs), None))
@@ -149,6 +161,28 @@ impl Settings {
     pub fn get_fps(&self) -> Option<u32> {
         self.min_frame_duration.map(|i| (1.0 / i.as_secs_f64()).round() as u32)
     }
+    /// sets the fixed timestep to 1 / tps or turns fixed updates off if none is inputed.
+    /// (tps being ticks per second)
+    pub fn set_fixed_tps(&mut self, tps: Option<u32>) {
+        self.fixed_timestep = tps.map(|i| Duration::from_secs_f64(1.0/i as f64));
+    }
+    pub fn get_fixed_tps(&self) -> Option<u32> {
+        self.fixed_timestep.map(|i| (1.0 / i.as_secs_f64()).round() as u32)
+    }
+    /// adds an extra window, made with `SimpleWindowBuilder::build()`, for thin engine to manage
+    /// after the current callback. events and draws for it are sent with its own window and
+    /// display. extra windows are dropped when the app is suspended and can be added again in
+    /// resumed.
+    pub fn add_window(&mut self, window: Window, display: Display) -> WindowId {
+        let id = window.id();
+        self.new_windows.push((window, display));
+        id
+    }
+    /// closes an extra window after the current callback. has no effect on the main window.
+    pub fn close_window(&mut self, id: WindowId) {
+        self.new_windows.retain(|(window, _)| window.id() != id);
+        self.closed_windows.push(id);
+    }
 }
 impl Default for Settings {
     fn default() -> Self {
@@ -156,37 +190,275 @@ impl Default for Settings {
         Self::new(gamepads, None)
     }
 }
+/// Timing data for the current update or draw, kept by thin engine so you dont have to track
+/// `Instant`s yourself.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Time {
+    /// time since the last update (or the fixed timestep) multiplied by the time scale.
+    pub delta: Duration,
+    /// time since the last update (or the fixed timestep) ignoring the time scale.
+    pub unscaled_delta: Duration,
+    /// total scaled time that has passed in updates.
+    pub elapsed: Duration,
+    /// the number of updates that have been run before this one.
+    pub frame: u64,
+    /// frames per second smoothed over the last few frames.
+    pub fps: f32,
+    /// the time scale `delta` was multiplied by. see `Settings::time_scale`
+    pub time_scale: f32,
+    /// how far between the previous and next fixed update the current draw is from 0 to 1.
+    /// always 0 without a fixed timestep.
+    pub alpha: f32
+}
+impl Time {
+    /// scaled delta time in seconds.
+    pub fn delta_secs(&self) -> f32 { self.delta.as_secs_f32() }
+    /// total scaled time in seconds.
+    pub fn elapsed_secs(&self) -> f32 { self.elapsed.as_secs_f32() }
+    pub(crate) fn step(&mut self, delta: Duration, time_scale: f32) {
+        let time_scale = time_scale.max(0.0);
+        self.unscaled_delta = delta;
+        self.delta = delta.mul_f32(time_scale);
+        self.elapsed += self.delta;
+        self.time_scale = time_scale;
+    }
+    pub(crate) fn measure_fps(&mut self, frame_time: Duration) {
+        let fps = 1.0 / frame_time.as_secs_f32().max(f32::EPSILON);
+        self.fps = if self.fps == 0.0 { fps } else { self.fps * 0.9 + fps * 0.1 };
+    }
+}
+/// An error from any part of thin engine or the libraries it uses. Returned by callbacks to stop
+/// the program, in which case `build()` will return the first error.
+#[derive(Debug)]
+pub enum ThinError {
+    ProgramCreationError(glium::ProgramCreationError),
+    MeshError(glium_types::vert_types::MeshError),
+    DrawError(glium::DrawError),
+    SwapBuffersError(glium::SwapBuffersError),
+    TextureCreationError(glium::texture::TextureCreationError),
+    /// boxed due to its size
+    GamepadError(Box<gilrs::Error>),
+    EventLoopError(winit::error::EventLoopError),
+    FramebufferError(glium::framebuffer::ValidationError),
+    /// failed to create an OpenGL context, e.g. for a headless run.
+    ContextError(glium::glutin::error::Error),
+    IncompatibleOpenGl(glium::IncompatibleOpenGl),
+    ReadError(glium::ReadError),
+    IoError(std::io::Error),
+    ObjError(meshes::obj::ObjError),
+    #[cfg(feature = "gltf")]
+    GltfError(gltf::Error),
+    #[cfg(feature = "image")]
+    TextureError(textures::TextureError),
+    #[cfg(feature = "capture")]
+    PngEncodingError(png::EncodingError),
+    #[cfg(feature = "capture")]
+    PngDecodingError(png::DecodingError),
+    /// a line of a recording couldn't be read.
+    #[cfg(feature = "record")]
+    RecordingError(usize, serde_json::Error),
+    #[cfg(feature = "text")]
+    FontError(&'static str),
+    #[cfg(feature = "text")]
+    TextDrawError(text_renderer::TextDrawError),
+    /// the font has no glyph for this character. see `TextRenderer::try_draw_only_valid()`
+    #[cfg(feature = "text")]
+    InvalidChar(char),
+    /// for errors that dont come from thin engine. e.g. your own errors.
+    Other(Box<dyn std::error::Error>)
+}
+impl std::fmt::Display for ThinError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use glium_types::vert_types::MeshError;
+        match self {
+            Self::ProgramCreationError(e) => write!(f, "failed to create program: {e}"),
+            Self::MeshError(MeshError::VertexErr(e)) => write!(f, "failed to create mesh: {e}"),
+            Self::MeshError(MeshError::IndexErr(e))  => write!(f, "failed to create mesh: {e}"),
+            Self::DrawError(e) => write!(f, "failed to draw: {e}"),
+            Self::SwapBuffersError(e) => write!(f, "failed to swap buffers: {e}"),
+            Self::TextureCreationError(e) => write!(f, "failed to create texture: {e}"),
+            Self::GamepadError(e) => write!(f, "failed to initialise gamepads: {e}"),
+            Self::EventLoopError(e) => write!(f, "event loop error: {e}"),
+            Self::FramebufferError(e) => write!(f, "failed to create framebuffer: {e}"),
+            Self::ContextError(e) => write!(f, "failed to create context: {e}"),
+            Self::IncompatibleOpenGl(e) => write!(f, "incompatible OpenGL: {e}"),
+            Self::ReadError(e) => write!(f, "failed to read pixels: {e}"),
+            Self::IoError(e) => write!(f, "io error: {e}"),
+            Self::ObjError(e) => write!(f, "failed to load obj: {e}"),
+            #[cfg(feature = "gltf")]
+            Self::GltfError(e) => write!(f, "failed to load gltf: {e}"),
+            #[cfg(feature = "image")]
+            Self::TextureError(e) => write!(f, "failed to load texture: {e}"),
+            #[cfg(feature = "capture")]
+            Self::PngEncodingError(e) => write!(f, "failed to save png: {e}"),
+            #[cfg(feature = "capture")]
+            Self::PngDecodingError(e) => write!(f, "failed to load png: {e}"),
+            #[cfg(feature = "record")]
+            Self::RecordingError(line, e) => write!(f, "failed to load recording on line {line}: {e}"),
+            #[cfg(feature = "text")]
+            Self::FontError(e) => write!(f, "failed to load font: {e}"),
+            #[cfg(feature = "text")]
+            Self::TextDrawError(e) => write!(f, "failed to draw text: {e}"),
+            #[cfg(feature = "text")]
+            Self::InvalidChar(c) => write!(f, "the font has no glyph for {c:?}"),
+            Self::Other(e) => e.fmt(f)
+        }
+    }
+}
+impl std::error::Error for ThinError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        use glium_types::vert_types::MeshError;
+        match self {
+            Self::ProgramCreationError(e) => Some(e),
+            Self::MeshError(MeshError::VertexErr(e)) => Some(e),
+            Self::MeshError(MeshError::IndexErr(e))  => Some(e),
+            Self::DrawError(e) => Some(e),
+            Self::SwapBuffersError(e) => Some(e),
+            Self::TextureCreationError(e) => Some(e),
+            Self::GamepadError(e) => Some(e.as_ref()),
+            Self::EventLoopError(e) => Some(e),
+            Self::FramebufferError(e) => Some(e),
+            Self::ContextError(e) => Some(e),
+            Self::IncompatibleOpenGl(e) => Some(e),
+            Self::ReadError(e) => Some(e),
+            Self::IoError(e) => Some(e),
+            Self::ObjError(e) => Some(e),
+            #[cfg(feature = "gltf")]
+            Self::GltfError(e) => Some(e),
+            #[cfg(feature = "image")]
+            Self::TextureError(e) => Some(e),
+            #[cfg(feature = "capture")]
+            Self::PngEncodingError(e) => Some(e),
+            #[cfg(feature = "capture")]
+            Self::PngDecodingError(e) => Some(e),
+            #[cfg(feature = "record")]
+            Self::RecordingError(_, e) => Some(e),
+            #[cfg(feature = "text")]
+            Self::FontError(_) => None,
+            #[cfg(feature = "text")]
+            Self::TextDrawError(e) => Some(e),
+            #[cfg(feature = "text")]
+            Self::InvalidChar(_) => None,
+            Self::Other(e) => Some(e.as_ref())
+        }
+    }
+}
+impl From<glium::ProgramCreationError> for ThinError {
+    fn from(err: glium::ProgramCreationError) -> Self { Self::ProgramCreationError(err) }
+}
+impl From<glium_types::vert_types::MeshError> for ThinError {
+    fn from(err: glium_types::vert_types::MeshError) -> Self { Self::MeshError(err) }
+}
+impl From<glium::DrawError> for ThinError {
+    fn from(err: glium::DrawError) -> Self { Self::DrawError(err) }
+}
+impl From<glium::SwapBuffersError> for ThinError {
+    fn from(err: glium::SwapBuffersError) -> Self { Self::SwapBuffersError(err) }
+}
+impl From<glium::texture::TextureCreationError> for ThinError {
+    fn from(err: glium::texture::TextureCreationError) -> Self { Self::TextureCreationError(err) }
+}
+impl From<gilrs::Error> for ThinError {
+    fn from(err: gilrs::Error) -> Self { Self::GamepadError(Box::new(err)) }
+}
+impl From<winit::error::EventLoopError> for ThinError {
+    fn from(err: winit::error::EventLoopError) -> Self { Self::EventLoopError(err) }
+}
+impl From<glium::framebuffer::ValidationError> for ThinError {
+    fn from(err: glium::framebuffer::ValidationError) -> Self { Self::FramebufferError(err) }
+}
+impl From<glium::glutin::error::Error> for ThinError {
+    fn from(err: glium::glutin::error::Error) -> Self { Self::ContextError(err) }
+}
+impl From<glium::IncompatibleOpenGl> for ThinError {
+    fn from(err: glium::IncompatibleOpenGl) -> Self { Self::IncompatibleOpenGl(err) }
+}
+impl From<glium::ReadError> for ThinError {
+    fn from(err: glium::ReadError) -> Self { Self::ReadError(err) }
+}
+impl From<std::io::Error> for ThinError {
+    fn from(err: std::io::Error) -> Self { Self::IoError(err) }
+}
+impl From<meshes::obj::ObjError> for ThinError {
+    fn from(err: meshes::obj::ObjError) -> Self { Self::ObjError(err) }
+}
+#[cfg(feature = "gltf")]
+impl From<gltf::Error> for ThinError {
+    fn from(err: gltf::Error) -> Self { Self::GltfError(err) }
+}
+#[cfg(feature = "image")]
+impl From<textures::TextureError> for ThinError {
+    fn from(err: textures::TextureError) -> Self { Self::TextureError(err) }
+}
+#[cfg(feature = "capture")]
+impl From<png::EncodingError> for ThinError {
+    fn from(err: png::EncodingError) -> Self { Self::PngEncodingError(err) }
+}
+#[cfg(feature = "capture")]
+impl From<png::DecodingError> for ThinError {
+    fn from(err: png::DecodingError) -> Self { Self::PngDecodingError(err) }
+}
+#[cfg(feature = "text")]
+impl From<text_renderer::TextDrawError> for ThinError {
+    fn from(err: text_renderer::TextDrawError) -> Self { Self::TextDrawError(err) }
+}
+#[cfg(feature = "text")]
+impl From<text_renderer::DrawValidError> for ThinError {
+    fn from(err: text_renderer::DrawValidError) -> Self {
+        match err {
+            text_renderer::DrawValidError::DrawError(e) => Self::TextDrawError(e),
+            text_renderer::DrawValidError::InvalidChar(c) => Self::InvalidChar(c)
+        }
+    }
+}
 pub mod meshes;
 pub mod shaders;
 pub mod application;
+pub mod scene;
+pub mod resources;
+pub mod assets;
+pub mod script;
+#[cfg(feature = "record")]
+pub mod recording;
 #[cfg(feature = "text")]
 pub mod text_renderer;
+#[cfg(feature = "image")]
+pub mod textures;
+#[cfg(feature = "capture")]
+pub mod capture;
+#[cfg(all(feature = "headless", not(target_vendor = "apple")))]
+pub mod headless;
+#[cfg(all(feature = "headless", not(target_vendor = "apple")))]
+pub mod golden;
 
 pub type Display = glium::Display<glium::glutin::surface::WindowSurface>;
 
 use winit_input_map::InputMap;
 use crate::application::ThinBuilder;
 use std::hash::Hash;
-pub fn builder<'a, H: Hash + Eq + Copy>(input_map: InputMap<H>) -> ThinBuilder<'a, H> {
-    ThinBuilder::<'a, H>::new(input_map)
+pub fn builder<'a, H: Hash + Eq + Copy, E: 'static>(input_map: InputMap<H>) -> ThinBuilder<'a, H, (), E> {
+    ThinBuilder::<'a, H, (), E>::new(input_map)
 }
 
 pub mod prelude {
     pub use crate::application::*;
+    pub use crate::scene::{Scene, SceneStack, Transition};
+    pub use crate::resources::{Resources, Resource};
+    pub use crate::assets::Assets;
     pub use glium::{
         draw_parameters, IndexBuffer, self,
         VertexBuffer, Program, Texture2d,
         uniform, Surface, Frame, DrawParameters,
         backend::glutin::simple_window_builder::SimpleWindowBuilder
     };
-    pub use crate::Settings;
+    pub use crate::{Settings, Time, Display, ThinError};
     pub use std::time::{Duration, Instant};
     pub use std::thread;
     pub use glium_types::prelude::*;
     pub use crate::{meshes, shaders};
     pub use winit::{self, event::MouseButton, keyboard::KeyCode};
     pub use gilrs::ev::{Button as GamepadButton, Axis as GamepadAxis};
-    pub use winit::{event_loop::*, window::{Fullscreen, CursorGrabMode}};
+    pub use winit::{event_loop::*, window::{Window, WindowId, Fullscreen, CursorGrabMode}};
     pub use crate::input_map::*;
 }
 /// resizable depth texture. recomended to  use with gliums `SimpleFrameBuffer` to draw onto a texture you can use
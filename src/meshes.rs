@@ -19,3 +19,244 @@ pub mod screen {
     ];
 }
 pub use glium_types::teapot;
+mod shapes;
+pub use shapes::*;
+pub mod obj;
+#[cfg(feature = "gltf")]
+pub mod gltf;
+use glium_types::prelude::*;
+use glium::{backend::Facade, IndexBuffer, VertexBuffer};
+use crate::ThinError;
+use std::collections::HashMap;
+
+/// the vertices, normals, uvs and indices of a mesh, made by the generators and loaders in this
+/// module. use it with `mesh!` like the other meshes or `upload()`, the indices are a triangle
+/// list. it can be changed on the CPU first, e.g. to combine meshes or make new normals.
+/// ```no_run
+/// use thin_engine::{prelude::*, meshes::{self, MeshData}};
+/// # fn f(display: &Display) -> Result<(), ThinError> {
+/// let sphere = meshes::uv_sphere(1.0, 32, 16);
+/// let (indices, vertices, normals, uvs) = mesh!(
+///     display, &sphere.indices, &sphere.vertices, &sphere.normals, &sphere.uvs
+/// )?;
+/// // a snowman in one draw call
+/// let head = meshes::uv_sphere(0.6, 32, 16).transformed(Mat4::from_pos(vec3(0.0, 1.4, 0.0)));
+/// let snowman = MeshData::merge([&sphere, &head]);
+/// let tangents = snowman.tangents();
+/// let (indices, vertices, normals, uvs) = snowman.upload(display)?;
+/// # Ok(()) }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MeshData {
+    pub indices: Vec<u32>,
+    pub vertices: Vec<Vertex>,
+    pub normals: Vec<Normal>,
+    pub uvs: Vec<TextureCoords>
+}
+/// a mesh uploaded to the GPU by `MeshData::upload()`.
+pub type MeshBuffers = (IndexBuffer<u32>, VertexBuffer<Vertex>, VertexBuffer<Normal>, VertexBuffer<TextureCoords>);
+/// an axis aligned bounding box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3
+}
+impl Aabb {
+    pub fn centre(&self) -> Vec3 { (self.min + self.max).scale(0.5) }
+    pub fn size(&self) -> Vec3 { self.max - self.min }
+    pub fn contains(&self, point: Vec3) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x)
+            && (self.min.y..=self.max.y).contains(&point.y)
+            && (self.min.z..=self.max.z).contains(&point.z)
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingSphere {
+    pub centre: Vec3,
+    pub radius: f32
+}
+fn position(vertex: &Vertex) -> Vec3 { vertex.position.into() }
+impl MeshData {
+    /// the normal of each triangle. triangles go clockwise around their front so the normal is
+    /// `cross(c - a, b - a)`, scaled by twice the area of the triangle.
+    fn face_normals(&self) -> impl Iterator<Item = ([u32; 3], Vec3)> + '_ {
+        self.indices.chunks_exact(3).map(|t| {
+            let [a, b, c] = [t[0], t[1], t[2]].map(|i| position(&self.vertices[i as usize]));
+            ([t[0], t[1], t[2]], (c - a).cross(b - a))
+        })
+    }
+    /// sets each normal to the average of the triangles around it, weighted by their area.
+    /// vertices at the same position share their normal so seams in the uvs stay smooth.
+    pub fn smooth_normals(&mut self) {
+        // rounded so that points that only differ by float error are treated as the same
+        let key = |v: &Vertex| {
+            let p = Vec3::from(v.position).scale(1e4);
+            (p.x.round() as i64, p.y.round() as i64, p.z.round() as i64)
+        };
+        let mut sums: HashMap<_, Vec3> = HashMap::new();
+        for (triangle, normal) in self.face_normals() {
+            for i in triangle { *sums.entry(key(&self.vertices[i as usize])).or_insert(Vec3::ZERO) += normal }
+        }
+        self.normals = self.vertices.iter().map(|v| {
+            let sum = sums.get(&key(v)).copied().unwrap_or(Vec3::ZERO);
+            if sum.length_squared() > 0.0 { sum.normalise().into() } else { Vec3::Y.into() }
+        }).collect();
+    }
+    /// gives every triangle its own vertices with the normal of the triangle, for sharp edges.
+    pub fn flat_normals(&mut self) {
+        let mut data = MeshData::default();
+        for (triangle, normal) in self.face_normals() {
+            let normal = if normal.length_squared() > 0.0 { normal.normalise() } else { Vec3::Y };
+            for i in triangle {
+                data.indices.push(data.vertices.len() as u32);
+                data.vertices.push(self.vertices[i as usize]);
+                data.normals.push(normal.into());
+                data.uvs.push(self.uvs.get(i as usize).copied().unwrap_or(TextureCoords::new(0.0, 0.0)));
+            }
+        }
+        *self = data;
+    }
+    /// tangents for normal mapping, calculated from the uvs and normals in the same way as
+    /// MikkTSpace: each triangle's tangents are weighted by its angle at the vertex and made
+    /// perpendicular to the normal. returns an empty `Vec` if there are fewer uvs or normals
+    /// than vertices, use `smooth_normals()` or `flat_normals()` first if there are no normals.
+    pub fn tangents(&self) -> Vec<Tangent> {
+        let len = self.vertices.len();
+        if self.uvs.len() < len || self.normals.len() < len { return Vec::new() }
+        let mut tangents = vec![Vec3::ZERO; self.vertices.len()];
+        let mut bitangents = vec![Vec3::ZERO; self.vertices.len()];
+        for t in self.indices.chunks_exact(3) {
+            let ids = [t[0], t[1], t[2]].map(|i| i as usize);
+            let [a, b, c] = ids.map(|i| position(&self.vertices[i]));
+            let [ua, ub, uc] = ids.map(|i| Vec2::from(self.uvs[i].texture_coords));
+            let (e1, e2) = (b - a, c - a);
+            let (d1, d2) = (ub - ua, uc - ua);
+            let det = d1.x * d2.y - d2.x * d1.y;
+            if det.abs() < f32::EPSILON { continue }
+            let tangent = (e1.scale(d2.y) - e2.scale(d1.y)).scale(1.0 / det);
+            let bitangent = (e2.scale(d1.x) - e1.scale(d2.x)).scale(1.0 / det);
+            for (corner, i) in ids.into_iter().enumerate() {
+                let p = [a, b, c];
+                let (to_next, to_prev) = (p[(corner + 1) % 3] - p[corner], p[(corner + 2) % 3] - p[corner]);
+                let cos = to_next.dot(to_prev) / (to_next.length() * to_prev.length()).max(f32::EPSILON);
+                let angle = cos.clamp(-1.0, 1.0).acos();
+                tangents[i] += tangent.scale(angle);
+                bitangents[i] += bitangent.scale(angle);
+            }
+        }
+        tangents.into_iter().zip(bitangents).zip(&self.normals).map(|((tangent, bitangent), normal)| {
+            let normal = Vec3::from(normal.normal);
+            let mut tangent = tangent - normal.scale(normal.dot(tangent));
+            if tangent.length_squared() < f32::EPSILON {
+                // no usable uvs so any direction along the surface will do
+                let axis = if normal.x.abs() < 0.9 { Vec3::X } else { Vec3::Y };
+                tangent = axis - normal.scale(normal.dot(axis));
+            }
+            let w = if normal.cross(tangent).dot(bitangent) < 0.0 { -1.0 } else { 1.0 };
+            tangent.normalise().extend(w).into()
+        }).collect()
+    }
+    /// the smallest box around the mesh lined up with the axes. empty meshes have a box of
+    /// size 0 at the origin.
+    pub fn aabb(&self) -> Aabb {
+        let mut points = self.vertices.iter().map(position);
+        let Some(first) = points.next() else { return Aabb { min: Vec3::ZERO, max: Vec3::ZERO } };
+        points.fold(Aabb { min: first, max: first }, |aabb, p| Aabb {
+            min: vec3(aabb.min.x.min(p.x), aabb.min.y.min(p.y), aabb.min.z.min(p.z)),
+            max: vec3(aabb.max.x.max(p.x), aabb.max.y.max(p.y), aabb.max.z.max(p.z))
+        })
+    }
+    /// a sphere around the mesh, centred on its bounding box.
+    pub fn bounding_sphere(&self) -> BoundingSphere {
+        let centre = self.aabb().centre();
+        let radius = self.vertices.iter()
+            .map(|v| position(v).distance_squared(centre))
+            .fold(0.0, f32::max).sqrt();
+        BoundingSphere { centre, radius }
+    }
+    /// moves, rotates and scales the mesh. normals are kept perpendicular to the surface and
+    /// triangles are flipped if the transform mirrors the mesh so they still face outwards.
+    pub fn transform(&mut self, transform: Mat4) {
+        let normal_matrix = transform.inverse().transpose();
+        for vertex in &mut self.vertices {
+            *vertex = (transform * position(vertex).extend(1.0)).truncate().into();
+        }
+        for normal in &mut self.normals {
+            let n = (normal_matrix * Vec3::from(normal.normal).extend(0.0)).truncate();
+            *normal = if n.length_squared() > 0.0 { n.normalise().into() } else { *normal };
+        }
+        if transform.determinant() < 0.0 {
+            for t in self.indices.chunks_exact_mut(3) { t.swap(1, 2) }
+        }
+    }
+    /// like `transform()` but returns a new mesh.
+    pub fn transformed(&self, transform: Mat4) -> Self {
+        let mut data = self.clone();
+        data.transform(transform);
+        data
+    }
+    /// adds another mesh to this one.
+    pub fn append(&mut self, other: &MeshData) {
+        let start = self.vertices.len() as u32;
+        self.indices.extend(other.indices.iter().map(|i| i + start));
+        self.vertices.extend_from_slice(&other.vertices);
+        self.normals.extend_from_slice(&other.normals);
+        self.uvs.extend_from_slice(&other.uvs);
+    }
+    /// combines meshes into one so they can be drawn together.
+    pub fn merge<'a>(meshes: impl IntoIterator<Item = &'a MeshData>) -> Self {
+        let mut data = MeshData::default();
+        for mesh in meshes { data.append(mesh) }
+        data
+    }
+    /// uploads the mesh to the GPU, the same as using `mesh!` on each part.
+    pub fn upload(&self, display: &impl Facade) -> Result<MeshBuffers, ThinError> {
+        Ok(mesh!(display, &self.indices, &self.vertices, &self.normals, &self.uvs)?)
+    }
+}
+/// a tangent used for normal mapping, pointing along the direction the u of the uvs increases.
+/// `w` is 1 or -1 and is the sign of the bitangent, `cross(normal, tangent.xyz) * w`.
+/// equivalent to `tangent` in a vertex shader.
+#[derive(Debug, Clone, Copy)]
+pub struct Tangent {
+    pub tangent: (f32, f32, f32, f32)
+}
+impl From<Vec4> for Tangent {
+    fn from(value: Vec4) -> Self {
+        Self { tangent: (value.x, value.y, value.z, value.w) }
+    }
+}
+impl Tangent {
+    pub const fn new(x: f32, y: f32, z: f32, w: f32) -> Self {
+        Self { tangent: (x, y, z, w) }
+    }
+}
+glium::implement_vertex!(Tangent, tangent);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn tangents_follow_the_uvs() {
+        let plane = plane(2.0, 2.0, 1);
+        let tangents = plane.tangents();
+        assert_eq!(tangents.len(), plane.vertices.len());
+        for (tangent, normal) in tangents.iter().zip(&plane.normals) {
+            let (x, y, z, w) = tangent.tangent;
+            assert!((vec3(x, y, z).length() - 1.0).abs() < 1e-5);
+            assert!(vec3(x, y, z).dot(Vec3::from(normal.normal)).abs() < 1e-5);
+            assert_eq!(w.abs(), 1.0);
+        }
+    }
+    #[test]
+    fn tangents_need_uvs_and_normals() {
+        let mut plane = plane(2.0, 2.0, 1);
+        plane.uvs.pop();
+        assert!(plane.tangents().is_empty());
+        plane.uvs.clear();
+        assert!(plane.tangents().is_empty());
+
+        let mut plane = super::plane(2.0, 2.0, 1);
+        plane.normals.clear();
+        assert!(plane.tangents().is_empty());
+    }
+}
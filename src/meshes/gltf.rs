@@ -0,0 +1,598 @@
+//! Loads glTF 2.0 scenes from `.gltf` and `.glb` files with embedded or external buffers.
+//! Meshes, node transforms, PBR materials, textures, skins and animations are loaded into
+//! plain data that can be uploaded with `mesh!` and glium's textures.
+//!
+//! glTF is right handed so the z axis is flipped to fit thin engine's left handed
+//! coordinates, the same as `obj`. uvs are flipped to start at the bottom left and images
+//! are uploaded upside down to match, so textures line up like any other. primitives made of
+//! points or lines are skipped.
+//! ```no_run
+//! use thin_engine::{prelude::*, meshes::gltf};
+//! # fn f(display: &Display) -> Result<(), ThinError> {
+//! let model = gltf::load("assets/robot.glb")?;
+//! let world = model.world_transforms();
+//! for (node, transform) in model.nodes.iter().zip(&world) {
+//!     let Some(mesh) = node.mesh else { continue };
+//!     for primitive in &model.meshes[mesh].primitives {
+//!         let data = &primitive.data;
+//!         let (indices, vertices, normals, uvs) = mesh!(
+//!             display, &data.indices, &data.vertices, &data.normals, &data.uvs
+//!         )?;
+//!         let material = model.material(primitive.material);
+//!         // base colour and emissive textures are srgb, the rest are linear
+//!         let albedo = material.base_colour_texture.map(|info| {
+//!             let texture = &model.textures[info.texture];
+//!             model.images[texture.image].to_srgb_texture(display)
+//!         }).transpose()?;
+//!         // draw with `*transform` as the model matrix
+//!     }
+//! }
+//! # Ok(()) }
+//! ```
+use super::{MeshData, Tangent};
+use crate::{ThinError, capture::Image};
+use glium::{
+    implement_vertex,
+    uniforms::{SamplerBehavior, SamplerWrapFunction, MinifySamplerFilter, MagnifySamplerFilter}
+};
+use glium_types::prelude::*;
+use std::path::Path;
+
+/// the 4 joints of a skin that move a vertex. equivalent to `joints` in a vertex shader.
+#[derive(Debug, Clone, Copy)]
+pub struct Joints {
+    pub joints: (u16, u16, u16, u16)
+}
+implement_vertex!(Joints, joints);
+/// how much each of the 4 joints in `Joints` moves a vertex. equivalent to `weights` in a
+/// vertex shader.
+#[derive(Debug, Clone, Copy)]
+pub struct Weights {
+    pub weights: (f32, f32, f32, f32)
+}
+implement_vertex!(Weights, weights);
+/// a loaded glTF file. everything refers to each other by index.
+#[derive(Debug, Clone, Default)]
+pub struct Gltf {
+    pub meshes: Vec<Mesh>,
+    pub materials: Vec<Material>,
+    pub textures: Vec<Texture>,
+    pub images: Vec<Image>,
+    pub nodes: Vec<Node>,
+    pub scenes: Vec<Scene>,
+    /// the scene to show when the file is loaded.
+    pub scene: Option<usize>,
+    pub skins: Vec<Skin>,
+    pub animations: Vec<Animation>
+}
+#[derive(Debug, Clone, Default)]
+pub struct Mesh {
+    pub name: Option<String>,
+    pub primitives: Vec<Primitive>
+}
+/// a part of a mesh with one material. attributes the primitive doesn't have are left empty,
+/// apart from normals which are made smooth and uvs which are set to 0.
+#[derive(Debug, Clone, Default)]
+pub struct Primitive {
+    /// the index of the material, `None` uses the default material.
+    pub material: Option<usize>,
+    pub data: MeshData,
+    pub tangents: Vec<Tangent>,
+    pub colours: Vec<VertexColour>,
+    pub joints: Vec<Joints>,
+    pub weights: Vec<Weights>
+}
+/// a texture used by a material and the uv set it reads from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureInfo {
+    /// the index of the texture.
+    pub texture: usize,
+    pub uv_set: u32
+}
+/// how a material uses the alpha of its base colour.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlphaMode {
+    Opaque,
+    /// fully transparent below the cutoff and opaque otherwise.
+    Mask(f32),
+    Blend
+}
+/// a metallic roughness PBR material.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Material {
+    pub name: Option<String>,
+    /// linear rgba, multiplied with the base colour texture.
+    pub base_colour: Vec4,
+    /// srgb.
+    pub base_colour_texture: Option<TextureInfo>,
+    pub metallic: f32,
+    pub roughness: f32,
+    /// roughness in the green channel and metallic in the blue channel.
+    pub metallic_roughness_texture: Option<TextureInfo>,
+    pub normal_texture: Option<TextureInfo>,
+    pub normal_scale: f32,
+    /// ambient occlusion in the red channel.
+    pub occlusion_texture: Option<TextureInfo>,
+    pub occlusion_strength: f32,
+    pub emissive: Vec3,
+    /// srgb.
+    pub emissive_texture: Option<TextureInfo>,
+    pub alpha_mode: AlphaMode,
+    /// whether back faces should be drawn.
+    pub double_sided: bool
+}
+impl Default for Material {
+    /// the material used by primitives without one.
+    fn default() -> Self {
+        Self {
+            name: None,
+            base_colour: Vec4::ONE,
+            base_colour_texture: None,
+            metallic: 1.0,
+            roughness: 1.0,
+            metallic_roughness_texture: None,
+            normal_texture: None,
+            normal_scale: 1.0,
+            occlusion_texture: None,
+            occlusion_strength: 1.0,
+            emissive: Vec3::ZERO,
+            emissive_texture: None,
+            alpha_mode: AlphaMode::Opaque,
+            double_sided: false
+        }
+    }
+}
+/// an image and how to sample it. use `Sampler(&texture, sampler)` as a uniform.
+#[derive(Debug, Clone, Copy)]
+pub struct Texture {
+    /// the index of the image.
+    pub image: usize,
+    pub sampler: SamplerBehavior
+}
+/// a point in the scene that can hold a mesh and be moved by animations.
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub name: Option<String>,
+    pub position: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+    pub mesh: Option<usize>,
+    /// the skin used by the mesh.
+    pub skin: Option<usize>
+}
+impl Node {
+    /// the transform relative to the parent.
+    pub fn transform(&self) -> Mat4 {
+        Mat4::from_transform(self.position, self.scale, self.rotation)
+    }
+}
+#[derive(Debug, Clone, Default)]
+pub struct Scene {
+    pub name: Option<String>,
+    /// the nodes at the root of the scene.
+    pub nodes: Vec<usize>
+}
+/// the joints that move a skinned mesh.
+#[derive(Debug, Clone, Default)]
+pub struct Skin {
+    pub name: Option<String>,
+    /// the nodes used as joints, in the order used by `Joints`.
+    pub joints: Vec<usize>,
+    /// moves each joint from the space of the mesh to the space of the joint.
+    pub inverse_bind_matrices: Vec<Mat4>,
+    /// the root of the skeleton.
+    pub skeleton: Option<usize>
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    Linear,
+    Step,
+    /// each keyframe has an in tangent, a value and an out tangent, in that order.
+    CubicSpline
+}
+/// the values of each keyframe of a channel.
+#[derive(Debug, Clone)]
+pub enum Keyframes {
+    Positions(Vec<Vec3>),
+    Rotations(Vec<Quat>),
+    Scales(Vec<Vec3>),
+    /// the morph target weights, with every weight of a keyframe next to each other.
+    Weights(Vec<f32>)
+}
+/// changes one property of a node over time.
+#[derive(Debug, Clone)]
+pub struct Channel {
+    /// the index of the node that is animated.
+    pub node: usize,
+    pub interpolation: Interpolation,
+    /// the time of each keyframe in seconds.
+    pub times: Vec<f32>,
+    pub keyframes: Keyframes
+}
+#[derive(Debug, Clone, Default)]
+pub struct Animation {
+    pub name: Option<String>,
+    pub channels: Vec<Channel>
+}
+impl Animation {
+    /// the time of the last keyframe in seconds.
+    pub fn duration(&self) -> f32 {
+        self.channels.iter().filter_map(|i| i.times.last()).fold(0.0, |a, b| a.max(*b))
+    }
+}
+impl Gltf {
+    /// the material of a primitive, or the default material if it has none.
+    pub fn material(&self, index: Option<usize>) -> Material {
+        index.and_then(|i| self.materials.get(i)).cloned().unwrap_or_default()
+    }
+    /// the transform of every node relative to the world, in the same order as `nodes`.
+    pub fn world_transforms(&self) -> Vec<Mat4> {
+        let mut world = vec![Mat4::IDENTITY; self.nodes.len()];
+        let mut stack: Vec<(usize, Mat4)> = (0..self.nodes.len())
+            .filter(|i| self.nodes[*i].parent.is_none())
+            .map(|i| (i, Mat4::IDENTITY)).collect();
+        while let Some((i, parent)) = stack.pop() {
+            world[i] = parent * self.nodes[i].transform();
+            stack.extend(self.nodes[i].children.iter().map(|child| (*child, world[i])));
+        }
+        world
+    }
+    /// every primitive of every node combined into one mesh in world space, ignoring
+    /// materials and skins.
+    pub fn merged(&self) -> MeshData {
+        let mut data = MeshData::default();
+        for (node, transform) in self.nodes.iter().zip(self.world_transforms()) {
+            let Some(mesh) = node.mesh else { continue };
+            for primitive in &self.meshes[mesh].primitives {
+                data.append(&primitive.data.transformed(transform));
+            }
+        }
+        data
+    }
+    /// the matrix of each joint of a skin for the vertex shader, from the transforms given by
+    /// `world_transforms()`. these move vertices straight into world space so skinned meshes
+    /// are drawn without the transform of their node.
+    pub fn joint_matrices(&self, skin: usize, world: &[Mat4]) -> Vec<Mat4> {
+        let skin = &self.skins[skin];
+        skin.joints.iter().zip(&skin.inverse_bind_matrices)
+            .map(|(joint, inverse_bind)| world[*joint] * *inverse_bind)
+            .collect()
+    }
+}
+/// loads a `.gltf` or `.glb` file. external buffers and images are found relative to it.
+pub fn load(path: impl AsRef<Path>) -> Result<Gltf, ThinError> {
+    let (document, buffers, images) = ::gltf::import(path)?;
+    Ok(convert(&document, &buffers, images))
+}
+/// loads a `.glb` file or a `.gltf` file with everything embedded from memory.
+pub fn load_slice(bytes: &[u8]) -> Result<Gltf, ThinError> {
+    let (document, buffers, images) = ::gltf::import_slice(bytes)?;
+    Ok(convert(&document, &buffers, images))
+}
+/// flips the z axis to go from right to left handed coordinates.
+fn flip(v: [f32; 3]) -> Vec3 { vec3(v[0], v[1], -v[2]) }
+/// flips a glTF `[x, y, z, w]` rotation to left handed coordinates.
+fn flip_rotation(q: [f32; 4]) -> Quat { Quat { r: q[3], i: -q[0], j: -q[1], k: q[2] } }
+/// flips a column major matrix to left handed coordinates.
+fn flip_matrix(mut m: [[f32; 4]; 4]) -> Mat4 {
+    for (c, column) in m.iter_mut().enumerate() { for (r, value) in column.iter_mut().enumerate() {
+        if (c == 2) != (r == 2) { *value = -*value }
+    } }
+    Mat4::from_column_major_array(m)
+}
+/// converts an image of any format to 8 bit RGBA.
+fn image(data: ::gltf::image::Data) -> Image {
+    use ::gltf::image::Format::*;
+    let channels = match data.format {
+        R8 | R16 => 1,
+        R8G8 | R16G16 => 2,
+        R8G8B8 | R16G16B16 | R32G32B32FLOAT => 3,
+        R8G8B8A8 | R16G16B16A16 | R32G32B32A32FLOAT => 4
+    };
+    let values: Vec<u8> = match data.format {
+        R8 | R8G8 | R8G8B8 | R8G8B8A8 => data.pixels,
+        R16 | R16G16 | R16G16B16 | R16G16B16A16 => data.pixels.chunks_exact(2)
+            .map(|i| (u16::from_ne_bytes([i[0], i[1]]) >> 8) as u8).collect(),
+        R32G32B32FLOAT | R32G32B32A32FLOAT => data.pixels.chunks_exact(4)
+            .map(|i| (f32::from_ne_bytes([i[0], i[1], i[2], i[3]]).clamp(0.0, 1.0) * 255.0).round() as u8)
+            .collect()
+    };
+    let pixels = values.chunks_exact(channels).flat_map(|i| match i {
+        [v] => [*v, *v, *v, 255],
+        [v, a] => [*v, *v, *v, *a],
+        [r, g, b] => [*r, *g, *b, 255],
+        [r, g, b, a] => [*r, *g, *b, *a],
+        _ => unreachable!()
+    }).collect();
+    Image { width: data.width, height: data.height, pixels }
+}
+fn convert(document: &::gltf::Document, buffers: &[::gltf::buffer::Data], images: Vec<::gltf::image::Data>) -> Gltf {
+    let get = |buffer: ::gltf::Buffer| buffers.get(buffer.index()).map(|i| &**i);
+    let info = |info: Option<::gltf::texture::Info>| info.map(|i| TextureInfo {
+        texture: i.texture().index(), uv_set: i.tex_coord()
+    });
+
+    let meshes = document.meshes().map(|mesh| Mesh {
+        name: mesh.name().map(String::from),
+        primitives: mesh.primitives().filter_map(|primitive| convert_primitive(&primitive, get)).collect()
+    }).collect();
+
+    let materials = document.materials().map(|material| {
+        use ::gltf::material::AlphaMode as Mode;
+        let pbr = material.pbr_metallic_roughness();
+        let normal = material.normal_texture();
+        let occlusion = material.occlusion_texture();
+        Material {
+            name: material.name().map(String::from),
+            base_colour: pbr.base_color_factor().into(),
+            base_colour_texture: info(pbr.base_color_texture()),
+            metallic: pbr.metallic_factor(),
+            roughness: pbr.roughness_factor(),
+            metallic_roughness_texture: info(pbr.metallic_roughness_texture()),
+            normal_texture: normal.as_ref().map(|i| TextureInfo { texture: i.texture().index(), uv_set: i.tex_coord() }),
+            normal_scale: normal.as_ref().map_or(1.0, |i| i.scale()),
+            occlusion_texture: occlusion.as_ref().map(|i| TextureInfo { texture: i.texture().index(), uv_set: i.tex_coord() }),
+            occlusion_strength: occlusion.as_ref().map_or(1.0, |i| i.strength()),
+            emissive: material.emissive_factor().into(),
+            emissive_texture: info(material.emissive_texture()),
+            alpha_mode: match material.alpha_mode() {
+                Mode::Opaque => AlphaMode::Opaque,
+                Mode::Mask => AlphaMode::Mask(material.alpha_cutoff().unwrap_or(0.5)),
+                Mode::Blend => AlphaMode::Blend
+            },
+            double_sided: material.double_sided()
+        }
+    }).collect();
+
+    let textures = document.textures().map(|texture| {
+        use ::gltf::texture::{MagFilter, MinFilter, WrappingMode};
+        let sampler = texture.sampler();
+        let wrap = |mode| match mode {
+            WrappingMode::ClampToEdge => SamplerWrapFunction::Clamp,
+            WrappingMode::MirroredRepeat => SamplerWrapFunction::Mirror,
+            WrappingMode::Repeat => SamplerWrapFunction::Repeat
+        };
+        let (wrap_s, wrap_t) = (wrap(sampler.wrap_s()), wrap(sampler.wrap_t()));
+        Texture {
+            image: texture.source().index(),
+            sampler: SamplerBehavior {
+                wrap_function: (wrap_s, wrap_t, SamplerWrapFunction::Repeat),
+                minify_filter: match sampler.min_filter() {
+                    Some(MinFilter::Nearest) => MinifySamplerFilter::Nearest,
+                    Some(MinFilter::Linear) => MinifySamplerFilter::Linear,
+                    Some(MinFilter::NearestMipmapNearest) => MinifySamplerFilter::NearestMipmapNearest,
+                    Some(MinFilter::LinearMipmapNearest) => MinifySamplerFilter::LinearMipmapNearest,
+                    Some(MinFilter::NearestMipmapLinear) => MinifySamplerFilter::NearestMipmapLinear,
+                    Some(MinFilter::LinearMipmapLinear) | None => MinifySamplerFilter::LinearMipmapLinear
+                },
+                magnify_filter: match sampler.mag_filter() {
+                    Some(MagFilter::Nearest) => MagnifySamplerFilter::Nearest,
+                    Some(MagFilter::Linear) | None => MagnifySamplerFilter::Linear
+                },
+                ..Default::default()
+            }
+        }
+    }).collect();
+
+    let mut nodes: Vec<Node> = document.nodes().map(|node| {
+        let (position, rotation, scale) = node.transform().decomposed();
+        Node {
+            name: node.name().map(String::from),
+            position: flip(position),
+            rotation: flip_rotation(rotation),
+            scale: scale.into(),
+            parent: None,
+            children: node.children().map(|i| i.index()).collect(),
+            mesh: node.mesh().map(|i| i.index()),
+            skin: node.skin().map(|i| i.index())
+        }
+    }).collect();
+    for i in 0..nodes.len() {
+        for child in nodes[i].children.clone() { nodes[child].parent = Some(i) }
+    }
+
+    let scenes = document.scenes().map(|scene| Scene {
+        name: scene.name().map(String::from),
+        nodes: scene.nodes().map(|i| i.index()).collect()
+    }).collect();
+
+    let skins = document.skins().map(|skin| {
+        let joints: Vec<usize> = skin.joints().map(|i| i.index()).collect();
+        let inverse_bind_matrices = match skin.reader(get).read_inverse_bind_matrices() {
+            Some(matrices) => matrices.map(flip_matrix).collect(),
+            None => vec![Mat4::IDENTITY; joints.len()]
+        };
+        Skin {
+            name: skin.name().map(String::from),
+            joints, inverse_bind_matrices,
+            skeleton: skin.skeleton().map(|i| i.index())
+        }
+    }).collect();
+
+    let animations = document.animations().map(|animation| Animation {
+        name: animation.name().map(String::from),
+        channels: animation.channels().filter_map(|channel| {
+            use ::gltf::animation::{util::ReadOutputs, Interpolation as Mode};
+            let reader = channel.reader(get);
+            let keyframes = match reader.read_outputs()? {
+                ReadOutputs::Translations(i) => Keyframes::Positions(i.map(flip).collect()),
+                ReadOutputs::Rotations(i) => Keyframes::Rotations(i.into_f32().map(flip_rotation).collect()),
+                ReadOutputs::Scales(i) => Keyframes::Scales(i.map(Vec3::from).collect()),
+                ReadOutputs::MorphTargetWeights(i) => Keyframes::Weights(i.into_f32().collect())
+            };
+            Some(Channel {
+                node: channel.target().node().index(),
+                interpolation: match channel.sampler().interpolation() {
+                    Mode::Linear => Interpolation::Linear,
+                    Mode::Step => Interpolation::Step,
+                    Mode::CubicSpline => Interpolation::CubicSpline
+                },
+                times: reader.read_inputs()?.collect(),
+                keyframes
+            })
+        }).collect()
+    }).collect();
+
+    Gltf {
+        meshes, materials, textures,
+        images: images.into_iter().map(image).collect(),
+        nodes, scenes,
+        scene: document.default_scene().map(|i| i.index()),
+        skins, animations
+    }
+}
+fn convert_primitive<'a, 's>(
+    primitive: &'a ::gltf::Primitive<'a>, get: impl Clone + Fn(::gltf::Buffer<'a>) -> Option<&'s [u8]>
+) -> Option<Primitive> {
+    use ::gltf::mesh::Mode;
+    let reader = primitive.reader(get);
+    let positions: Vec<Vec3> = reader.read_positions()?.map(flip).collect();
+    let count = positions.len();
+    let order: Vec<u32> = match reader.read_indices() {
+        Some(indices) => indices.into_u32().collect(),
+        None => (0..count as u32).collect()
+    };
+    let indices = match primitive.mode() {
+        Mode::Triangles => order,
+        Mode::TriangleStrip => (0..order.len().saturating_sub(2)).flat_map(|i| {
+            let (a, b, c) = (order[i], order[i + 1], order[i + 2]);
+            if i.is_multiple_of(2) { [a, b, c] } else { [b, a, c] }
+        }).collect(),
+        Mode::TriangleFan => (1..order.len().saturating_sub(1))
+            .flat_map(|i| [order[0], order[i], order[i + 1]]).collect(),
+        Mode::Points | Mode::Lines | Mode::LineLoop | Mode::LineStrip => return None
+    };
+    if indices.iter().any(|i| *i as usize >= count) { return None }
+
+    let normals: Vec<Vec3> = match reader.read_normals() {
+        Some(normals) => normals.map(flip).collect(),
+        None => {
+            let mut normals = vec![Vec3::ZERO; count];
+            for t in indices.chunks_exact(3) {
+                let [a, b, c] = [t[0], t[1], t[2]].map(|i| positions[i as usize]);
+                // triangles are clockwise after flipping so the cross product faces inwards
+                let normal = (c - a).cross(b - a);
+                for i in t { normals[*i as usize] += normal }
+            }
+            normals.into_iter().map(|i| if i == Vec3::ZERO { Vec3::Y } else { i.normalise() }).collect()
+        }
+    };
+    let uvs = match reader.read_tex_coords(0) {
+        Some(uvs) => uvs.into_f32().map(|[u, v]| TextureCoords::new(u, 1.0 - v)).collect(),
+        None => vec![TextureCoords::new(0.0, 0.0); count]
+    };
+    let data = MeshData {
+        indices,
+        vertices: positions.into_iter().map(Vertex::from).collect(),
+        normals: normals.into_iter().map(Normal::from).collect(),
+        uvs
+    };
+    Some(Primitive {
+        material: primitive.material().index(),
+        data,
+        tangents: reader.read_tangents().map_or(Vec::new(), |i| {
+            i.map(|[x, y, z, w]| Tangent::new(x, y, -z, -w)).collect()
+        }),
+        colours: reader.read_colors(0).map_or(Vec::new(), |i| {
+            i.into_rgba_f32().map(|[r, g, b, a]| VertexColour::new(r, g, b, a)).collect()
+        }),
+        joints: reader.read_joints(0).map_or(Vec::new(), |i| {
+            i.into_u16().map(|[a, b, c, d]| Joints { joints: (a, b, c, d) }).collect()
+        }),
+        weights: reader.read_weights(0).map_or(Vec::new(), |i| {
+            i.into_f32().map(|[a, b, c, d]| Weights { weights: (a, b, c, d) }).collect()
+        })
+    })
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    /// a quad with one triangle list, one triangle strip and one triangle fan primitive on a
+    /// child node. `DATA` is replaced with the buffer.
+    const SCENE: &str = r#"{
+        "asset": { "version": "2.0" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [
+            { "translation": [1, 2, 3], "rotation": [0, 0.70710677, 0, 0.70710677], "children": [1] },
+            { "translation": [0, 0, 1], "mesh": 0 }
+        ],
+        "meshes": [{ "primitives": [
+            { "attributes": { "POSITION": 0, "NORMAL": 1, "TANGENT": 2 }, "indices": 3, "mode": 4 },
+            { "attributes": { "POSITION": 0 }, "indices": 4, "mode": 5 },
+            { "attributes": { "POSITION": 0 }, "mode": 6 }
+        ] }],
+        "accessors": [
+            { "bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3", "min": [0, 0, 1], "max": [1, 1, 1] },
+            { "bufferView": 1, "componentType": 5126, "count": 4, "type": "VEC3" },
+            { "bufferView": 2, "componentType": 5126, "count": 4, "type": "VEC4" },
+            { "bufferView": 3, "componentType": 5123, "count": 3, "type": "SCALAR" },
+            { "bufferView": 4, "componentType": 5123, "count": 4, "type": "SCALAR" }
+        ],
+        "bufferViews": [
+            { "buffer": 0, "byteOffset": 0, "byteLength": 48 },
+            { "buffer": 0, "byteOffset": 48, "byteLength": 48 },
+            { "buffer": 0, "byteOffset": 96, "byteLength": 64 },
+            { "buffer": 0, "byteOffset": 160, "byteLength": 6 },
+            { "buffer": 0, "byteOffset": 168, "byteLength": 8 }
+        ],
+        "buffers": [{ "byteLength": 176, "uri": "data:application/octet-stream;base64,DATA" }]
+    }"#;
+    fn base64(bytes: &[u8]) -> String {
+        const DIGITS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        bytes.chunks(3).flat_map(|i| {
+            let n = (i[0] as u32) << 16 | (*i.get(1).unwrap_or(&0) as u32) << 8 | *i.get(2).unwrap_or(&0) as u32;
+            (0..4).map(move |c| if c <= i.len() { DIGITS[(n >> (18 - 6 * c) & 63) as usize] as char } else { '=' })
+        }).collect()
+    }
+    fn scene() -> Gltf {
+        let positions = [[0.0, 0.0, 1.0], [1.0, 0.0, 1.0], [1.0, 1.0, 1.0], [0.0, 1.0, 1.0f32]];
+        let mut buffer: Vec<u8> = positions.iter().flatten().flat_map(|i| i.to_le_bytes()).collect();
+        buffer.extend([0.0, 0.0, 1.0f32].repeat(4).iter().flat_map(|i| i.to_le_bytes()));
+        buffer.extend([1.0, 0.0, 1.0, 1.0f32].repeat(4).iter().flat_map(|i| i.to_le_bytes()));
+        buffer.extend([0, 1, 2, 0, 0, 1, 3, 2u16].iter().flat_map(|i| i.to_le_bytes()));
+        load_slice(SCENE.replace("DATA", &base64(&buffer)).as_bytes()).unwrap()
+    }
+    fn close(a: Vec3, b: Vec3) -> bool { (a - b).length() < 1e-5 }
+    #[test]
+    fn z_is_flipped() {
+        let gltf = scene();
+        let primitive = &gltf.meshes[0].primitives[0];
+        assert_eq!(primitive.data.vertices[1].position, (1.0, 0.0, -1.0));
+        assert!(primitive.data.normals.iter().all(|i| i.normal == (0.0, 0.0, -1.0)));
+        assert!(primitive.tangents.iter().all(|i| i.tangent == (1.0, 0.0, -1.0, -1.0)));
+        assert_eq!(gltf.nodes[0].position, vec3(1.0, 2.0, -3.0));
+        let Quat { r, i, j, k } = gltf.nodes[0].rotation;
+        assert_eq!((r, i, j, k), (0.70710677, 0.0, -0.70710677, 0.0));
+    }
+    #[test]
+    fn strips_and_fans_become_triangles() {
+        let gltf = scene();
+        let indices: Vec<&[u32]> = gltf.meshes[0].primitives.iter().map(|i| &*i.data.indices).collect();
+        assert_eq!(indices, [&[0, 1, 2][..], &[0, 1, 3, 3, 1, 2], &[0, 1, 2, 0, 2, 3]]);
+        // made up normals face the same way as the given ones
+        for primitive in &gltf.meshes[0].primitives[1..] {
+            assert!(primitive.data.normals.iter().all(|i| close(i.normal.into(), vec3(0.0, 0.0, -1.0))));
+        }
+    }
+    #[test]
+    fn children_move_with_parents() {
+        let gltf = scene();
+        assert_eq!(gltf.nodes[1].parent, Some(0));
+        let world = gltf.world_transforms();
+        assert_eq!(world[0], gltf.nodes[0].transform());
+        assert_eq!(world[1], world[0] * gltf.nodes[1].transform());
+    }
+    #[test]
+    fn merged_is_in_world_space() {
+        let gltf = scene();
+        let merged = gltf.merged();
+        assert_eq!(merged.vertices.len(), 12);
+        assert_eq!(merged.indices.len(), 15);
+        assert_eq!(&merged.indices[3..6], [4, 5, 7]);
+        // (0, 0, -1) moved back by the child then turned a quarter around y and moved by the parent
+        assert!(close(merged.vertices[0].position.into(), vec3(3.0, 2.0, -3.0)), "{:?}", merged.vertices[0]);
+    }
+}
@@ -0,0 +1,459 @@
+//! Loads Wavefront OBJ models and their MTL materials. Each object, group and material in the
+//! file becomes its own mesh so they can be drawn with different materials. Polygons are
+//! triangulated and vertices that share a position, uv and normal are only stored once.
+//!
+//! OBJ files are right handed so the z axis is flipped to fit thin engine's left handed
+//! coordinates. Faces without normals are given flat normals.
+//! ```no_run
+//! use thin_engine::{prelude::*, meshes::obj};
+//! # fn f(display: &Display) -> Result<(), ThinError> {
+//! let model = obj::load("assets/house.obj")?;
+//! for mesh in &model.meshes {
+//!     let colour = mesh.material.as_ref()
+//!         .and_then(|name| model.materials.get(name))
+//!         .map_or(Vec3::ONE, |material| material.diffuse);
+//!     let data = &mesh.data;
+//!     let (indices, vertices, normals, uvs) = mesh!(
+//!         display, &data.indices, &data.vertices, &data.normals, &data.uvs
+//!     )?;
+//! }
+//! # Ok(()) }
+//! ```
+use super::MeshData;
+use crate::ThinError;
+use glium_types::prelude::*;
+use std::{collections::HashMap, path::{Path, PathBuf}};
+
+/// an error in an OBJ or MTL file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjError {
+    /// the file the error is in, if it was loaded from a file.
+    pub file: Option<PathBuf>,
+    /// the line the error is on, starting at 1.
+    pub line: usize,
+    pub message: String
+}
+impl ObjError {
+    fn in_file(mut self, file: &Path) -> Self {
+        self.file = Some(file.to_path_buf());
+        self
+    }
+}
+impl std::fmt::Display for ObjError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{}:{}: {}", file.display(), self.line, self.message),
+            None => write!(f, "line {}: {}", self.line, self.message)
+        }
+    }
+}
+impl std::error::Error for ObjError {}
+/// the surface properties of a material from an MTL file. texture paths are relative to the
+/// MTL file when loaded with `load()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Material {
+    pub name: String,
+    /// `Ka`
+    pub ambient: Vec3,
+    /// `Kd`
+    pub diffuse: Vec3,
+    /// `Ks`
+    pub specular: Vec3,
+    /// `Ke`
+    pub emissive: Vec3,
+    /// `Ns`, the specular exponent.
+    pub shininess: f32,
+    /// `d`, or 1 - `Tr`.
+    pub opacity: f32,
+    /// `Ni`
+    pub optical_density: f32,
+    /// `illum`
+    pub illumination: u32,
+    pub ambient_texture: Option<PathBuf>,
+    pub diffuse_texture: Option<PathBuf>,
+    pub specular_texture: Option<PathBuf>,
+    pub emissive_texture: Option<PathBuf>,
+    pub opacity_texture: Option<PathBuf>,
+    /// `bump`, `map_Bump` or `norm`
+    pub normal_texture: Option<PathBuf>
+}
+impl Material {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ambient: Vec3::ZERO,
+            diffuse: Vec3::ONE,
+            specular: Vec3::ZERO,
+            emissive: Vec3::ZERO,
+            shininess: 0.0,
+            opacity: 1.0,
+            optical_density: 1.0,
+            illumination: 2,
+            ambient_texture: None,
+            diffuse_texture: None,
+            specular_texture: None,
+            emissive_texture: None,
+            opacity_texture: None,
+            normal_texture: None
+        }
+    }
+}
+/// a part of a model with one object or group name and one material.
+#[derive(Debug, Clone)]
+pub struct ObjMesh {
+    /// the name of the object and groups the faces are in, joined by a space.
+    pub name: String,
+    pub material: Option<String>,
+    pub data: MeshData
+}
+/// a loaded OBJ model.
+#[derive(Debug, Clone, Default)]
+pub struct Obj {
+    pub meshes: Vec<ObjMesh>,
+    /// the materials from the MTL files used by the model, by name.
+    pub materials: HashMap<String, Material>,
+    /// the MTL files named by `mtllib`.
+    pub material_libraries: Vec<String>
+}
+impl Obj {
+    /// every mesh combined into one, ignoring materials.
+    pub fn merged(&self) -> MeshData {
+        MeshData::merge(self.meshes.iter().map(|i| &i.data))
+    }
+}
+/// loads an OBJ file and the MTL files it uses, which are found relative to it. MTL files that
+/// don't exist are skipped and materials that aren't in any MTL file get `Material::new()`.
+pub fn load(path: impl AsRef<Path>) -> Result<Obj, ThinError> {
+    let path = path.as_ref();
+    let mut obj = parse(&std::fs::read_to_string(path)?).map_err(|e| e.in_file(path))?;
+    let folder = path.parent().unwrap_or(Path::new(""));
+    for library in &obj.material_libraries {
+        let mtl_path = folder.join(library);
+        let source = match std::fs::read_to_string(&mtl_path) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            source => source?
+        };
+        let mut materials = parse_mtl(&source).map_err(|e| e.in_file(&mtl_path))?;
+        for material in materials.values_mut() {
+            for texture in [
+                &mut material.ambient_texture, &mut material.diffuse_texture,
+                &mut material.specular_texture, &mut material.emissive_texture,
+                &mut material.opacity_texture, &mut material.normal_texture
+            ].into_iter().flatten() {
+                *texture = folder.join(&*texture);
+            }
+        }
+        obj.materials.extend(materials);
+    }
+    for name in obj.meshes.iter().filter_map(|i| i.material.as_ref()) {
+        if !obj.materials.contains_key(name) { obj.materials.insert(name.clone(), Material::new(name)); }
+    }
+    Ok(obj)
+}
+/// reads the next `N` numbers of a line, using `defaults` for any that are missing.
+fn numbers<'a, const N: usize>(
+    parts: &mut impl Iterator<Item = &'a str>, required: usize, defaults: [f32; N], line: usize
+) -> Result<[f32; N], ObjError> {
+    let mut values = defaults;
+    for (i, value) in values.iter_mut().enumerate() {
+        match parts.next() {
+            Some(part) => *value = part.parse().map_err(|_| error(line, format!("{part:?} is not a number")))?,
+            None if i < required => return Err(error(line, format!("expected {required} numbers"))),
+            None => break
+        }
+    }
+    Ok(values)
+}
+fn error(line: usize, message: impl Into<String>) -> ObjError {
+    ObjError { file: None, line, message: message.into() }
+}
+/// turns a 1 based or negative relative OBJ index into an index into `len` items.
+fn index(part: &str, len: usize, line: usize) -> Result<usize, ObjError> {
+    let i: isize = part.parse().map_err(|_| error(line, format!("{part:?} is not an index")))?;
+    let resolved = if i < 0 { len as isize + i } else { i - 1 };
+    if i == 0 || resolved < 0 || resolved >= len as isize {
+        return Err(error(line, format!("index {i} is out of range, there are {len}")))
+    }
+    Ok(resolved as usize)
+}
+/// flips the z axis to go from right to left handed coordinates.
+fn flip(v: Vec3) -> Vec3 { vec3(v.x, v.y, -v.z) }
+/// normalises a normal, pointing zero length ones up like `MeshData::smooth_normals()`.
+fn unit(v: Vec3) -> Vec3 { if v.length_squared() > 0.0 { v.normalise() } else { Vec3::Y } }
+/// the normal of a polygon using newell's method, facing the side its points go anticlockwise
+/// around.
+fn polygon_normal(points: &[Vec3]) -> Vec3 {
+    let mut normal = Vec3::ZERO;
+    for (i, a) in points.iter().enumerate() {
+        let b = points[(i + 1) % points.len()];
+        normal += vec3((a.y - b.y) * (a.z + b.z), (a.z - b.z) * (a.x + b.x), (a.x - b.x) * (a.y + b.y));
+    }
+    normal
+}
+/// splits a polygon into triangles by clipping ears, returning indices into `points`. falls
+/// back to a fan if the polygon is too broken to clip.
+fn triangulate(points: &[Vec3], normal: Vec3) -> Vec<[usize; 3]> {
+    if points.len() == 3 { return vec![[0, 1, 2]] }
+    let side = |a: Vec3, b: Vec3, p: Vec3| (b - a).cross(p - a).dot(normal);
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    let mut triangles = Vec::with_capacity(points.len() - 2);
+    while remaining.len() > 3 {
+        let len = remaining.len();
+        let ear = (0..len).find(|&i| {
+            let (a, b, c) = (remaining[(i + len - 1) % len], remaining[i], remaining[(i + 1) % len]);
+            let (pa, pb, pc) = (points[a], points[b], points[c]);
+            side(pa, pb, pc) > 0.0 && !remaining.iter().any(|&p| {
+                p != a && p != b && p != c
+                    && side(pa, pb, points[p]) >= 0.0
+                    && side(pb, pc, points[p]) >= 0.0
+                    && side(pc, pa, points[p]) >= 0.0
+            })
+        });
+        let Some(i) = ear else { break };
+        triangles.push([remaining[(i + len - 1) % len], remaining[i], remaining[(i + 1) % len]]);
+        remaining.remove(i);
+    }
+    for i in 1..remaining.len() - 1 { triangles.push([remaining[0], remaining[i], remaining[i + 1]]) }
+    triangles
+}
+/// a mesh being read, with the vertices it already has.
+struct MeshBuilder {
+    mesh: ObjMesh,
+    vertices: HashMap<(usize, Option<usize>, Option<usize>), u32>
+}
+impl MeshBuilder {
+    fn new(name: &str, material: &Option<String>) -> Self {
+        let mesh = ObjMesh { name: name.to_string(), material: material.clone(), data: MeshData::default() };
+        Self { mesh, vertices: HashMap::new() }
+    }
+}
+/// parses the text of an OBJ file. `mtllib` files are listed in `material_libraries` but not
+/// loaded, use `load()` or `parse_mtl()` for that.
+pub fn parse(source: &str) -> Result<Obj, ObjError> {
+    let mut obj = Obj::default();
+    let (mut positions, mut uvs, mut normals) = (Vec::new(), Vec::new(), Vec::new());
+    let (mut object, mut groups, mut material) = (String::new(), String::new(), None);
+    let mut current = MeshBuilder::new("", &None);
+
+    let mut finish = |current: &mut MeshBuilder, next: MeshBuilder| {
+        let done = std::mem::replace(current, next);
+        if !done.mesh.data.indices.is_empty() { obj.meshes.push(done.mesh) }
+    };
+    let join = |object: &str, groups: &str| [object, groups].into_iter()
+        .filter(|i| !i.is_empty()).collect::<Vec<_>>().join(" ");
+
+    for (i, text) in source.lines().enumerate() {
+        let line = i + 1;
+        let text = text.split('#').next().unwrap_or("");
+        let mut parts = text.split_whitespace();
+        let Some(keyword) = parts.next() else { continue };
+        match keyword {
+            "v" => {
+                let [x, y, z] = numbers(&mut parts, 3, [0.0; 3], line)?;
+                positions.push(vec3(x, y, z));
+            },
+            "vt" => {
+                let [u, v] = numbers(&mut parts, 1, [0.0; 2], line)?;
+                uvs.push(vec2(u, v));
+            },
+            "vn" => {
+                let [x, y, z] = numbers(&mut parts, 3, [0.0; 3], line)?;
+                normals.push(unit(vec3(x, y, z)));
+            },
+            "o" | "g" => {
+                let name = parts.collect::<Vec<_>>().join(" ");
+                if keyword == "o" { object = name; groups.clear() } else { groups = name }
+                finish(&mut current, MeshBuilder::new(&join(&object, &groups), &material));
+            },
+            "usemtl" => {
+                material = Some(parts.collect::<Vec<_>>().join(" "));
+                finish(&mut current, MeshBuilder::new(&join(&object, &groups), &material));
+            },
+            "mtllib" => obj.material_libraries.extend(parts.map(String::from)),
+            "f" => {
+                let mut corners = Vec::new();
+                for part in parts {
+                    let mut ids = part.split('/');
+                    let position = index(ids.next().unwrap_or(""), positions.len(), line)?;
+                    let uv = match ids.next() {
+                        Some("") | None => None,
+                        Some(id) => Some(index(id, uvs.len(), line)?)
+                    };
+                    let normal = match ids.next() {
+                        Some("") | None => None,
+                        Some(id) => Some(index(id, normals.len(), line)?)
+                    };
+                    corners.push((position, uv, normal));
+                }
+                if corners.len() < 3 { return Err(error(line, "a face needs at least 3 vertices")) }
+
+                let points: Vec<Vec3> = corners.iter().map(|(p, _, _)| positions[*p]).collect();
+                let face_normal = polygon_normal(&points);
+                // faces without normals don't share vertices as they have flat normals
+                let flat = flip(unit(face_normal));
+                let data = &mut current.mesh.data;
+                let ids: Vec<u32> = corners.iter().map(|&key| {
+                    let (position, uv, normal) = key;
+                    let add = |data: &mut MeshData| {
+                        data.vertices.push(flip(positions[position]).into());
+                        data.normals.push(normal.map_or(flat, |n| flip(normals[n])).into());
+                        data.uvs.push(uv.map_or(Vec2::ZERO, |i| uvs[i]).into());
+                        data.vertices.len() as u32 - 1
+                    };
+                    if normal.is_none() { return add(data) }
+                    *current.vertices.entry(key).or_insert_with(|| add(data))
+                }).collect();
+                for [a, b, c] in triangulate(&points, face_normal) {
+                    data.indices.extend([ids[a], ids[b], ids[c]]);
+                }
+            },
+            // smoothing groups, lines, points and curves aren't used
+            _ => ()
+        }
+    }
+    finish(&mut current, MeshBuilder::new("", &None));
+    Ok(obj)
+}
+/// parses the text of an MTL file into its materials by name.
+pub fn parse_mtl(source: &str) -> Result<HashMap<String, Material>, ObjError> {
+    let mut materials = HashMap::new();
+    let mut current: Option<Material> = None;
+    for (i, text) in source.lines().enumerate() {
+        let line = i + 1;
+        let text = text.split('#').next().unwrap_or("");
+        let mut parts = text.split_whitespace();
+        let Some(keyword) = parts.next() else { continue };
+        if keyword == "newmtl" {
+            let name = parts.collect::<Vec<_>>().join(" ");
+            if let Some(done) = current.replace(Material::new(name)) { materials.insert(done.name.clone(), done); }
+            continue
+        }
+        let Some(material) = &mut current else {
+            return Err(error(line, format!("{keyword:?} is before any newmtl")))
+        };
+        let colour = |parts: &mut std::str::SplitWhitespace| -> Result<Vec3, ObjError> {
+            let [r, g, b] = numbers(parts, 1, [f32::NAN; 3], line)?;
+            // a single value is used for every channel
+            Ok(if g.is_nan() { Vec3::splat(r) } else { vec3(r, g, b.max(0.0)) })
+        };
+        // texture options come before the file name so the last part is used
+        let texture = |parts: std::str::SplitWhitespace| parts.last().map(PathBuf::from)
+            .ok_or_else(|| error(line, format!("{keyword} needs a file name")));
+        match keyword {
+            "Ka" => material.ambient = colour(&mut parts)?,
+            "Kd" => material.diffuse = colour(&mut parts)?,
+            "Ks" => material.specular = colour(&mut parts)?,
+            "Ke" => material.emissive = colour(&mut parts)?,
+            "Ns" => material.shininess = numbers(&mut parts, 1, [0.0], line)?[0],
+            "d" => material.opacity = numbers(&mut parts, 1, [1.0], line)?[0],
+            "Tr" => material.opacity = 1.0 - numbers(&mut parts, 1, [0.0], line)?[0],
+            "Ni" => material.optical_density = numbers(&mut parts, 1, [1.0], line)?[0],
+            "illum" => material.illumination = numbers(&mut parts, 1, [2.0], line)?[0] as u32,
+            "map_Ka" => material.ambient_texture = Some(texture(parts)?),
+            "map_Kd" => material.diffuse_texture = Some(texture(parts)?),
+            "map_Ks" => material.specular_texture = Some(texture(parts)?),
+            "map_Ke" => material.emissive_texture = Some(texture(parts)?),
+            "map_d" => material.opacity_texture = Some(texture(parts)?),
+            "bump" | "map_Bump" | "map_bump" | "norm" => material.normal_texture = Some(texture(parts)?),
+            _ => ()
+        }
+    }
+    if let Some(done) = current { materials.insert(done.name.clone(), done); }
+    Ok(materials)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const QUAD: &str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n";
+    #[test]
+    fn errors_have_line_numbers() {
+        let error = parse(&format!("{QUAD}\n# comment\nf 1 2 9")).unwrap_err();
+        assert_eq!(error.line, 7);
+        assert!(error.message.contains("out of range"), "{}", error.message);
+
+        assert_eq!(parse("v 0 0 0\nv 1 zero 0").unwrap_err().line, 2);
+        assert_eq!(parse(&format!("{QUAD}f 1 2")).unwrap_err().line, 5);
+        assert_eq!(parse(&format!("{QUAD}f 0 1 2")).unwrap_err().line, 5);
+        assert_eq!(parse_mtl("# materials\nKd 1 0 0").unwrap_err().line, 2);
+    }
+    #[test]
+    fn negative_indices() {
+        let absolute = parse(&format!("{QUAD}f 2 3 4")).unwrap();
+        let relative = parse(&format!("{QUAD}f -3 -2 -1")).unwrap();
+        let positions = |obj: &Obj| obj.meshes[0].data.vertices.iter().map(|i| i.position).collect::<Vec<_>>();
+        assert_eq!(positions(&absolute), positions(&relative));
+        assert!(parse(&format!("{QUAD}f -5 -2 -1")).is_err());
+    }
+    #[test]
+    fn split_by_group_and_material() {
+        let source = format!("{QUAD}mtllib a.mtl b.mtl\no house\nf 1 2 3\ng roof\nusemtl red\nf 1 3 4\nusemtl blue\nf 1 2 4\no empty\n");
+        let obj = parse(&source).unwrap();
+        assert_eq!(obj.material_libraries, ["a.mtl", "b.mtl"]);
+        let meshes: Vec<_> = obj.meshes.iter().map(|i| (i.name.as_str(), i.material.as_deref())).collect();
+        assert_eq!(meshes, [("house", None), ("house roof", Some("red")), ("house roof", Some("blue"))]);
+        assert!(obj.meshes.iter().all(|i| i.data.indices.len() == 3));
+    }
+    #[test]
+    fn shared_vertices_are_reused() {
+        let smooth = parse(&format!("{QUAD}vt 0 0\nvn 0 0 1\nf 1/1/1 2/1/1 3/1/1\nf 1/1/1 3/1/1 4/1/1")).unwrap();
+        let data = &smooth.meshes[0].data;
+        assert_eq!((data.vertices.len(), data.indices.len()), (4, 6));
+        // the z axis is flipped
+        assert_eq!(data.normals[0].normal, (0.0, 0.0, -1.0));
+
+        // faces without normals get flat normals so they don't share
+        let flat = parse(&format!("{QUAD}f 1 2 3\nf 1 3 4")).unwrap();
+        assert_eq!(flat.meshes[0].data.vertices.len(), 6);
+    }
+    #[test]
+    fn concave_polygons_are_clipped() {
+        // a U shape, a fan from the first corner would cover the gap
+        let points = [
+            vec3(0.0, 0.0, 0.0), vec3(3.0, 0.0, 0.0), vec3(3.0, 2.0, 0.0), vec3(2.0, 2.0, 0.0),
+            vec3(2.0, 1.0, 0.0), vec3(1.0, 1.0, 0.0), vec3(1.0, 2.0, 0.0), vec3(0.0, 2.0, 0.0)
+        ];
+        let normal = polygon_normal(&points);
+        let triangles = triangulate(&points, normal);
+        assert_eq!(triangles.len(), points.len() - 2);
+        let mut area = 0.0;
+        for [a, b, c] in triangles {
+            let (a, b, c) = (points[a], points[b], points[c]);
+            let doubled = (b - a).cross(c - a).dot(normal.normalise());
+            // every triangle faces the same way as the polygon
+            assert!(doubled > 0.0);
+            area += doubled / 2.0;
+        }
+        assert_eq!(area, 5.0);
+    }
+    #[test]
+    fn materials() {
+        let materials = parse_mtl("newmtl red\nKd 1 0 0\nKa 0.5\nTr 0.25\nmap_Kd -s 2 2 1 red.png\n\nnewmtl plain\n").unwrap();
+        let red = &materials["red"];
+        assert_eq!(red.diffuse, vec3(1.0, 0.0, 0.0));
+        assert_eq!(red.ambient, Vec3::splat(0.5));
+        assert_eq!(red.opacity, 0.75);
+        assert_eq!(red.diffuse_texture, Some(PathBuf::from("red.png")));
+        assert_eq!(materials["plain"], Material::new("plain"));
+    }
+    #[test]
+    fn zero_normals_point_up() {
+        let obj = parse(&format!("{QUAD}vn 0 0 0\nf 1//1 2//1 3//1\nf 1 2 2")).unwrap();
+        let data = &obj.meshes[0].data;
+        assert!(data.normals.iter().all(|i| i.normal == (0.0, 1.0, 0.0)), "{:?}", data.normals);
+    }
+    #[test]
+    fn missing_material_libraries_are_skipped() {
+        let folder = std::env::temp_dir().join(format!("thin-engine-{}-obj", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("found.mtl"), "newmtl red\nKd 1 0 0\nmap_Kd red.png").unwrap();
+        let path = folder.join("model.obj");
+        std::fs::write(&path, format!("{QUAD}mtllib missing.mtl found.mtl\nusemtl red\nf 1 2 3\nusemtl blue\nf 1 3 4")).unwrap();
+        let obj = load(&path).unwrap();
+        assert_eq!(obj.materials["red"].diffuse_texture, Some(folder.join("red.png")));
+        assert_eq!(obj.materials["blue"], Material::new("blue"));
+
+        std::fs::write(folder.join("found.mtl"), "Kd 1 0 0").unwrap();
+        assert!(load(&path).is_err());
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+}
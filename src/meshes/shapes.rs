@@ -0,0 +1,292 @@
+use super::MeshData;
+use glium_types::prelude::*;
+use std::f32::consts::{PI, TAU};
+
+/// adds a grid of `cols` by `rows` quads. `f` gives the position and normal of each point and
+/// the change in position along `i` crossed with the change along `j` has to face outwards.
+fn grid(data: &mut MeshData, cols: u32, rows: u32, f: impl Fn(u32, u32) -> (Vec3, Vec3)) {
+    let start = data.vertices.len() as u32;
+    for j in 0..=rows { for i in 0..=cols {
+        let (position, normal) = f(i, j);
+        data.vertices.push(position.into());
+        data.normals.push(normal.into());
+        data.uvs.push(vec2(i as f32 / cols as f32, 1.0 - j as f32 / rows as f32).into());
+    } }
+    for j in 0..rows { for i in 0..cols {
+        let a = start + j * (cols + 1) + i;
+        let (b, c, d) = (a + 1, a + cols + 1, a + cols + 2);
+        // points can meet at the poles of spheres and the point of cones
+        let point = |i: u32| { let (x, y, z) = data.vertices[i as usize].position; vec3(x, y, z) };
+        let same = |a: u32, b: u32| point(a).distance_squared(point(b)) < 1e-12;
+        if !same(a, b) { data.indices.extend([a, c, b]) }
+        if !same(c, d) { data.indices.extend([b, c, d]) }
+    } }
+}
+/// adds a flat disc at `y` facing up or down.
+fn cap(data: &mut MeshData, radius: f32, y: f32, sectors: u32, up: bool) {
+    let start = data.vertices.len() as u32;
+    let normal = if up { Vec3::Y } else { -Vec3::Y };
+    data.vertices.push(vec3(0.0, y, 0.0).into());
+    data.normals.push(normal.into());
+    data.uvs.push(vec2(0.5, 0.5).into());
+    for i in 0..=sectors {
+        let (sin, cos) = (i as f32 / sectors as f32 * TAU).sin_cos();
+        data.vertices.push(vec3(cos * radius, y, sin * radius).into());
+        data.normals.push(normal.into());
+        data.uvs.push(vec2(0.5 + cos * 0.5, 0.5 + sin * 0.5).into());
+    }
+    for i in 1..=sectors {
+        let (a, b) = (start + i, start + i + 1);
+        data.indices.extend(if up { [start, a, b] } else { [start, b, a] });
+    }
+}
+/// a point on a sphere where `polar` is the angle from the top and `azimuth` the angle around.
+fn sphere_point(polar: f32, azimuth: f32) -> Vec3 {
+    let (sin_p, cos_p) = polar.sin_cos();
+    let (sin_a, cos_a) = azimuth.sin_cos();
+    vec3(sin_p * cos_a, cos_p, sin_p * sin_a)
+}
+/// a box with the given width, height and depth. each face has its own vertices so it has
+/// sharp edges.
+pub fn cuboid(size: Vec3) -> MeshData {
+    let half = size.scale(0.5);
+    let mut data = MeshData::default();
+    // the normal, right and down directions of each face
+    let faces = [
+        (Vec3::X,  Vec3::Z,  -Vec3::Y), (-Vec3::X, -Vec3::Z, -Vec3::Y),
+        (Vec3::Z,  -Vec3::X, -Vec3::Y), (-Vec3::Z, Vec3::X,  -Vec3::Y),
+        (Vec3::Y,  Vec3::X,  -Vec3::Z), (-Vec3::Y, Vec3::X,  Vec3::Z)
+    ];
+    for (normal, right, down) in faces {
+        grid(&mut data, 1, 1, |i, j| {
+            let point = normal + right.scale(i as f32 * 2.0 - 1.0) + down.scale(j as f32 * 2.0 - 1.0);
+            (point * half, normal)
+        });
+    }
+    data
+}
+/// a cube with sides of length `size`.
+pub fn cube(size: f32) -> MeshData {
+    cuboid(Vec3::splat(size))
+}
+/// a sphere made of `sectors` slices around and `stacks` slices from top to bottom. the uvs
+/// wrap around it once like a map of the earth.
+pub fn uv_sphere(radius: f32, sectors: u32, stacks: u32) -> MeshData {
+    let (sectors, stacks) = (sectors.max(3), stacks.max(2));
+    let mut data = MeshData::default();
+    grid(&mut data, sectors, stacks, |i, j| {
+        let normal = sphere_point(j as f32 / stacks as f32 * PI, i as f32 / sectors as f32 * TAU);
+        (normal.scale(radius), normal)
+    });
+    data
+}
+/// a sphere made by splitting the triangles of an icosahedron `subdivisions` times, giving
+/// triangles of a more even size than a uv sphere. the uvs are spherical so there is a seam
+/// where they wrap around.
+pub fn icosphere(radius: f32, subdivisions: u32) -> MeshData {
+    let t = (1.0 + 5.0_f32.sqrt()) / 2.0;
+    let mut points: Vec<Vec3> = [
+        (-1.0, t, 0.0), (1.0, t, 0.0), (-1.0, -t, 0.0), (1.0, -t, 0.0),
+        (0.0, -1.0, t), (0.0, 1.0, t), (0.0, -1.0, -t), (0.0, 1.0, -t),
+        (t, 0.0, -1.0), (t, 0.0, 1.0), (-t, 0.0, -1.0), (-t, 0.0, 1.0)
+    ].into_iter().map(|(x, y, z)| vec3(x, y, z).normalise()).collect();
+    let mut triangles: Vec<[u32; 3]> = vec![
+        [0, 5, 11], [0, 1, 5], [0, 7, 1], [0, 10, 7], [0, 11, 10],
+        [1, 9, 5], [5, 4, 11], [11, 2, 10], [10, 6, 7], [7, 8, 1],
+        [3, 4, 9], [3, 2, 4], [3, 6, 2], [3, 8, 6], [3, 9, 8],
+        [4, 5, 9], [2, 11, 4], [6, 10, 2], [8, 7, 6], [9, 1, 8]
+    ];
+    for _ in 0..subdivisions {
+        let mut midpoints = std::collections::HashMap::new();
+        let mut midpoint = |a: u32, b: u32| *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+            points.push((points[a as usize] + points[b as usize]).normalise());
+            points.len() as u32 - 1
+        });
+        triangles = triangles.into_iter().flat_map(|[a, b, c]| {
+            let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+            [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+        }).collect();
+    }
+    let mut data = MeshData::default();
+    for normal in points {
+        data.vertices.push(normal.scale(radius).into());
+        data.normals.push(normal.into());
+        let u = 0.5 + normal.z.atan2(normal.x) / TAU;
+        let v = 0.5 + normal.y.clamp(-1.0, 1.0).asin() / PI;
+        data.uvs.push(vec2(u, v).into());
+    }
+    data.indices = triangles.into_iter().flatten().collect();
+    data
+}
+/// a flat plane on the x and z axes facing up, split into `subdivisions` by `subdivisions`
+/// squares. useful for floors, water and terrain.
+pub fn plane(width: f32, depth: f32, subdivisions: u32) -> MeshData {
+    let subdivisions = subdivisions.max(1);
+    let mut data = MeshData::default();
+    grid(&mut data, subdivisions, subdivisions, |i, j| {
+        let (x, z) = (i as f32 / subdivisions as f32, j as f32 / subdivisions as f32);
+        (vec3((x - 0.5) * width, 0.0, (0.5 - z) * depth), Vec3::Y)
+    });
+    data
+}
+/// a cylinder standing on the y axis with flat caps.
+pub fn cylinder(radius: f32, height: f32, sectors: u32) -> MeshData {
+    let sectors = sectors.max(3);
+    let half = height / 2.0;
+    let mut data = MeshData::default();
+    grid(&mut data, sectors, 1, |i, j| {
+        let normal = sphere_point(PI / 2.0, i as f32 / sectors as f32 * TAU);
+        (normal.scale(radius) + vec3(0.0, half - j as f32 * height, 0.0), normal)
+    });
+    cap(&mut data, radius, half, sectors, true);
+    cap(&mut data, radius, -half, sectors, false);
+    data
+}
+/// a cone standing on the y axis with its point at the top and a flat base.
+pub fn cone(radius: f32, height: f32, sectors: u32) -> MeshData {
+    let sectors = sectors.max(3);
+    let half = height / 2.0;
+    let mut data = MeshData::default();
+    grid(&mut data, sectors, 1, |i, j| {
+        let (sin, cos) = (i as f32 / sectors as f32 * TAU).sin_cos();
+        let normal = vec3(cos * height, radius, sin * height).normalise();
+        let r = j as f32 * radius;
+        (vec3(cos * r, half - j as f32 * height, sin * r), normal)
+    });
+    cap(&mut data, radius, -half, sectors, false);
+    data
+}
+/// a cylinder with rounded ends standing on the y axis. `height` is the length of the
+/// cylinder between the ends, so the total height is `height + radius * 2`. `rings` is the
+/// amount of slices in each rounded end.
+pub fn capsule(radius: f32, height: f32, sectors: u32, rings: u32) -> MeshData {
+    let (sectors, rings) = (sectors.max(3), rings.max(1));
+    let half = height / 2.0;
+    let mut data = MeshData::default();
+    // the top end, the cylinder and then the bottom end
+    grid(&mut data, sectors, rings * 2 + 1, |i, j| {
+        let (polar, y) = if j <= rings { (j as f32 / rings as f32 * PI / 2.0, half) }
+            else { ((j - 1) as f32 / rings as f32 * PI / 2.0, -half) };
+        let normal = sphere_point(polar, i as f32 / sectors as f32 * TAU);
+        (normal.scale(radius) + vec3(0.0, y, 0.0), normal)
+    });
+    data
+}
+/// a ring shaped torus lying on the x and z axes. `radius` is the distance from the centre to
+/// the middle of the tube, `sectors` is the amount of slices around the ring and `sides` the
+/// amount around the tube.
+pub fn torus(radius: f32, tube_radius: f32, sectors: u32, sides: u32) -> MeshData {
+    let (sectors, sides) = (sectors.max(3), sides.max(3));
+    let mut data = MeshData::default();
+    grid(&mut data, sectors, sides, |i, j| {
+        let (sin_a, cos_a) = (i as f32 / sectors as f32 * TAU).sin_cos();
+        let (sin_t, cos_t) = (j as f32 / sides as f32 * TAU).sin_cos();
+        let normal = vec3(cos_t * cos_a, -sin_t, cos_t * sin_a);
+        let centre = vec3(cos_a * radius, 0.0, sin_a * radius);
+        (centre + normal.scale(tube_radius), normal)
+    });
+    data
+}
+/// a flat circle on the x and y axes facing towards -z, the same way as the screen mesh.
+pub fn circle(radius: f32, sectors: u32) -> MeshData {
+    let sectors = sectors.max(3);
+    let points: Vec<Vec2> = (0..sectors).map(|i| {
+        let (sin, cos) = (i as f32 / sectors as f32 * TAU).sin_cos();
+        vec2(cos * radius, sin * radius)
+    }).collect();
+    polygon(&points)
+}
+/// a flat convex polygon on the x and y axes facing towards -z. `points` go anticlockwise and
+/// the uvs stretch over the bounding box of the points.
+pub fn polygon(points: &[Vec2]) -> MeshData {
+    let mut data = MeshData::default();
+    if points.len() < 3 { return data }
+    let min = points.iter().fold(points[0], |a, b| vec2(a.x.min(b.x), a.y.min(b.y)));
+    let max = points.iter().fold(points[0], |a, b| vec2(a.x.max(b.x), a.y.max(b.y)));
+    let size = vec2((max.x - min.x).max(f32::EPSILON), (max.y - min.y).max(f32::EPSILON));
+    for point in points {
+        data.vertices.push(point.extend(0.0).into());
+        data.normals.push((-Vec3::Z).into());
+        data.uvs.push(vec2((point.x - min.x) / size.x, (point.y - min.y) / size.y).into());
+    }
+    for i in 1..points.len() as u32 - 1 { data.indices.extend([0, i, i + 1]) }
+    data
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn point(data: &MeshData, i: u32) -> Vec3 { Vec3::from(data.vertices[i as usize].position) }
+    /// triangles go clockwise seen from the front, the same as the teapot.
+    fn face_normal(data: &MeshData, [a, b, c]: [u32; 3]) -> Vec3 {
+        let a = point(data, a);
+        (point(data, c) - a).cross(point(data, b) - a)
+    }
+    fn triangles(data: &MeshData) -> impl Iterator<Item = [u32; 3]> + '_ {
+        data.indices.chunks_exact(3).map(|i| [i[0], i[1], i[2]])
+    }
+    /// checks the parts of a shape line up and each face points the same way as its normals
+    /// and, if `centre` is given, away from the centre of the shape at its position.
+    fn check(name: &str, data: &MeshData, centre: Option<fn(Vec3) -> Vec3>) {
+        let len = data.vertices.len();
+        assert!(len > 0 && !data.indices.is_empty(), "{name} is empty");
+        assert_eq!(data.indices.len() % 3, 0, "{name}");
+        assert_eq!(data.normals.len(), len, "{name}");
+        assert_eq!(data.uvs.len(), len, "{name}");
+        assert!(data.indices.iter().all(|i| (*i as usize) < len), "{name} has an index out of range");
+        for normal in &data.normals {
+            assert!((Vec3::from(normal.normal).length() - 1.0).abs() < 1e-4, "{name} normal {normal:?}");
+        }
+        for triangle in triangles(data) {
+            let face = face_normal(data, triangle);
+            assert!(face.length() > 0.0, "{name} has a degenerate triangle {triangle:?}");
+            let normals = triangle.map(|i| Vec3::from(data.normals[i as usize].normal));
+            assert!(face.dot(normals[0] + normals[1] + normals[2]) > 0.0, "{name} {triangle:?} faces against its normals");
+            if let Some(centre) = centre {
+                let middle = triangle.map(|i| point(data, i)).into_iter().fold(Vec3::ZERO, |a, b| a + b).scale(1.0 / 3.0);
+                assert!(face.dot(middle - centre(middle)) > 0.0, "{name} {triangle:?} faces inwards");
+            }
+        }
+    }
+    const ORIGIN: Option<fn(Vec3) -> Vec3> = Some(|_| Vec3::ZERO);
+    #[test]
+    fn closed_shapes_face_outwards() {
+        check("cuboid", &cuboid(vec3(1.0, 2.0, 3.0)), ORIGIN);
+        check("cube", &cube(2.0), ORIGIN);
+        check("uv sphere", &uv_sphere(1.5, 16, 8), ORIGIN);
+        check("uv sphere with too few slices", &uv_sphere(1.0, 0, 0), ORIGIN);
+        check("icosphere", &icosphere(1.0, 0), ORIGIN);
+        check("subdivided icosphere", &icosphere(2.0, 2), ORIGIN);
+        check("cylinder", &cylinder(1.0, 3.0, 12), ORIGIN);
+        check("cone", &cone(1.0, 2.0, 12), ORIGIN);
+        check("capsule", &capsule(0.5, 2.0, 12, 4), ORIGIN);
+        // faces point away from the middle of the tube
+        check("torus", &torus(2.0, 0.5, 16, 8), Some(|point| vec3(point.x, 0.0, point.z).normalise().scale(2.0)));
+    }
+    #[test]
+    fn flat_shapes_face_their_normal() {
+        let plane = plane(2.0, 3.0, 4);
+        check("plane", &plane, None);
+        assert_eq!(plane.vertices.len(), 5 * 5);
+        assert!(plane.normals.iter().all(|i| i.normal == (0.0, 1.0, 0.0)));
+
+        let circle = circle(1.0, 8);
+        check("circle", &circle, None);
+        assert!(circle.normals.iter().all(|i| i.normal == (0.0, 0.0, -1.0)));
+
+        let square = [vec2(0.0, 0.0), vec2(1.0, 0.0), vec2(1.0, 1.0), vec2(0.0, 1.0)];
+        let polygon = polygon(&square);
+        check("polygon", &polygon, None);
+        assert_eq!(polygon.uvs[2].texture_coords, (1.0, 1.0));
+        assert!(super::polygon(&square[..2]).vertices.is_empty());
+    }
+    #[test]
+    fn spheres_have_their_radius() {
+        for data in [uv_sphere(1.5, 16, 8), icosphere(1.5, 2)] {
+            for i in 0..data.vertices.len() as u32 {
+                assert!((point(&data, i).length() - 1.5).abs() < 1e-4);
+            }
+        }
+        let bounds = cuboid(vec3(1.0, 2.0, 3.0)).aabb();
+        assert_eq!(bounds.size(), vec3(1.0, 2.0, 3.0));
+    }
+}
@@ -0,0 +1,293 @@
+//! Records input to a file so that bug reports and playthroughs can be replayed. Each line of a
+//! recording is a JSON object holding an input and the frame it happened on.
+//!
+//! Inputs are recorded as they reach the input map, so keys and buttons are saved as presses and
+//! releases and gamepad sticks are saved as pressed once they pass `press_sensitivity`. Replays
+//! use a fixed clock, so record with a fixed timestep for the replay to match.
+//! ```no_run
+//! use thin_engine::{prelude::*, script::InputScript};
+//! let event_loop = EventLoop::new().unwrap();
+//! let input = input_map!((KeyCode::Space, KeyCode::Space));
+//! let replay = std::env::args().nth(1);
+//! let builder = thin_engine::builder(input).with_fixed_tps(60);
+//! let builder = match replay {
+//!     // replay a recording passed as an argument
+//!     Some(path) => builder.with_replay(InputScript::load(path).unwrap()),
+//!     None => builder.with_recording("last-run.jsonl")
+//! };
+//! builder.build(event_loop).unwrap();
+//! ```
+use crate::{ThinError, script::{InputScript, ScriptedInput}};
+use winit::{event::{WindowEvent, DeviceEvent, MouseButton, MouseScrollDelta}, keyboard::PhysicalKey};
+use winit_input_map::{InputCode, DeviceInput, GamepadInput, SpecifyDevice, SpecifyGamepad, axis_pos, axis_neg};
+use glium_types::vectors::{Vec2, vec2};
+use serde::{Serialize, Deserialize};
+use std::{collections::HashSet, fs::File, io::{BufRead, BufReader, BufWriter, Write}, path::Path};
+
+const GAMEPAD_INPUTS: [GamepadInput; 28] = {
+    use GamepadInput::*;
+    [
+        LeftStickLeft, LeftStickRight, LeftStickUp, LeftStickDown, LeftStickPress,
+        RightStickLeft, RightStickRight, RightStickUp, RightStickDown, RightStickPress,
+        DPadLeft, DPadRight, DPadUp, DPadDown, LeftZ, RightZ, South, East, North, West,
+        LeftBumper, LeftTrigger, RightBumper, RightTrigger, Select, Start, Mode, Other
+    ]
+};
+const BUTTONS: [gilrs::Button; 19] = {
+    use gilrs::Button::*;
+    [
+        South, East, North, West, C, Z, LeftTrigger, LeftTrigger2, RightTrigger, RightTrigger2,
+        Select, Start, Mode, LeftThumb, RightThumb, DPadUp, DPadDown, DPadLeft, DPadRight
+    ]
+};
+const AXES: [gilrs::Axis; 8] = {
+    use gilrs::Axis::*;
+    [LeftStickX, LeftStickY, LeftZ, RightStickX, RightStickY, RightZ, DPadX, DPadY]
+};
+/// the saved form of an input code. gamepad inputs are saved by name.
+#[derive(Serialize, Deserialize)]
+enum Code {
+    Key(PhysicalKey),
+    Button(MouseButton),
+    Gamepad(String)
+}
+impl Code {
+    fn new(code: &InputCode) -> Option<Self> {
+        match code {
+            InputCode::Device { input: DeviceInput::Key(key), .. } => Some(Self::Key(*key)),
+            InputCode::Device { input: DeviceInput::Button(button), .. } => Some(Self::Button(*button)),
+            InputCode::Gamepad { input, .. } => Some(Self::Gamepad(format!("{input:?}"))),
+            // mouse movement and scrolling are saved as motion and scroll instead
+            _ => None
+        }
+    }
+    fn input_code(self) -> Option<InputCode> {
+        Some(match self {
+            Self::Key(key) => DeviceInput::Key(key).with_sid(SpecifyDevice::Any),
+            Self::Button(button) => DeviceInput::Button(button).with_sid(SpecifyDevice::Any),
+            Self::Gamepad(name) => GAMEPAD_INPUTS.into_iter()
+                .find(|i| format!("{i:?}") == name)?
+                .with_sid(SpecifyGamepad::Any)
+        })
+    }
+}
+#[derive(Serialize, Deserialize)]
+enum Input {
+    Press(Code),
+    Release(Code),
+    MouseMotion([f32; 2]),
+    CursorMoved([f32; 2]),
+    Scroll([f32; 2]),
+    Text(String)
+}
+impl Input {
+    fn new(input: &ScriptedInput) -> Option<Self> {
+        Some(match input {
+            ScriptedInput::Press(code) => Self::Press(Code::new(code)?),
+            ScriptedInput::Release(code) => Self::Release(Code::new(code)?),
+            ScriptedInput::MouseMotion(v) => Self::MouseMotion([v.x, v.y]),
+            ScriptedInput::CursorMoved(v) => Self::CursorMoved([v.x, v.y]),
+            ScriptedInput::Scroll(v) => Self::Scroll([v.x, v.y]),
+            ScriptedInput::Text(text) => Self::Text(text.clone())
+        })
+    }
+    fn scripted(self) -> Option<ScriptedInput> {
+        Some(match self {
+            Self::Press(code) => ScriptedInput::Press(code.input_code()?),
+            Self::Release(code) => ScriptedInput::Release(code.input_code()?),
+            Self::MouseMotion([x, y]) => ScriptedInput::MouseMotion(vec2(x, y)),
+            Self::CursorMoved([x, y]) => ScriptedInput::CursorMoved(vec2(x, y)),
+            Self::Scroll([x, y]) => ScriptedInput::Scroll(vec2(x, y)),
+            Self::Text(text) => ScriptedInput::Text(text)
+        })
+    }
+}
+/// a line of a recording.
+#[derive(Serialize, Deserialize)]
+enum Line {
+    Input { frame: u64, input: Input },
+    End { frame: u64 }
+}
+fn write_line(writer: &mut impl Write, line: &Line) -> Result<(), ThinError> {
+    let json = serde_json::to_string(line).map_err(|e| ThinError::Other(Box::new(e)))?;
+    writeln!(writer, "{json}")?;
+    Ok(())
+}
+impl InputScript {
+    /// saves the script as a recording. inputs of mouse movement or scroll codes are skipped as
+    /// they can't be pressed.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ThinError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        for (frame, input) in self.inputs() {
+            let Some(input) = Input::new(input) else { continue };
+            write_line(&mut writer, &Line::Input { frame: *frame, input })?;
+        }
+        if let Some(frame) = self.end() { write_line(&mut writer, &Line::End { frame })? }
+        writer.flush()?;
+        Ok(())
+    }
+    /// loads a recording made with `ThinBuilder::with_recording()` or `save()`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ThinError> {
+        let mut script = Self::new();
+        for (i, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() { continue }
+            let error = |e| ThinError::RecordingError(i + 1, e);
+            match serde_json::from_str(&line).map_err(error)? {
+                Line::Input { frame, input } => {
+                    let input = input.scripted().ok_or_else(|| error(serde::de::Error::custom("unknown gamepad input")))?;
+                    script.push(frame, input);
+                },
+                Line::End { frame } => script.set_end(Some(frame))
+            }
+        }
+        Ok(script)
+    }
+}
+/// writes inputs to a recording as they happen.
+pub(crate) struct Recorder {
+    writer: BufWriter<File>,
+    /// keys, buttons and gamepad inputs that are held down.
+    pressed: HashSet<InputCode>,
+    /// the input map ignores gamepads while the window is unfocused.
+    focused: bool
+}
+impl Recorder {
+    pub(crate) fn new(path: impl AsRef<Path>) -> Result<Self, ThinError> {
+        Ok(Self { writer: BufWriter::new(File::create(path)?), pressed: HashSet::new(), focused: true })
+    }
+    fn record(&mut self, frame: u64, input: ScriptedInput) -> Result<(), ThinError> {
+        let Some(input) = Input::new(&input) else { return Ok(()) };
+        write_line(&mut self.writer, &Line::Input { frame, input })
+    }
+    fn set_pressed(&mut self, frame: u64, code: InputCode, pressed: bool) -> Result<(), ThinError> {
+        if pressed && self.pressed.insert(code) {
+            self.record(frame, ScriptedInput::Press(code))
+        } else if !pressed && self.pressed.remove(&code) {
+            self.record(frame, ScriptedInput::Release(code))
+        } else { Ok(()) }
+    }
+    pub(crate) fn window_event(&mut self, frame: u64, event: &WindowEvent) -> Result<(), ThinError> {
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => {
+                let code = DeviceInput::Key(event.physical_key).with_sid(SpecifyDevice::Any);
+                self.set_pressed(frame, code, event.state.is_pressed())?;
+                if let Some(text) = &event.text { self.record(frame, ScriptedInput::Text(text.to_string()))? }
+                Ok(())
+            },
+            WindowEvent::MouseInput { state, button, .. } => {
+                let code = DeviceInput::Button(*button).with_sid(SpecifyDevice::Any);
+                self.set_pressed(frame, code, state.is_pressed())
+            },
+            WindowEvent::CursorMoved { position, .. } => {
+                self.record(frame, ScriptedInput::CursorMoved(vec2(position.x as f32, position.y as f32)))
+            },
+            WindowEvent::MouseWheel { delta, .. } => self.record(frame, ScriptedInput::Scroll(scroll(delta))),
+            // the input map releases everything when focus is lost
+            WindowEvent::Focused(focused) => {
+                self.focused = *focused;
+                if *focused { return Ok(()) }
+                for code in self.pressed.drain().collect::<Vec<_>>() {
+                    self.record(frame, ScriptedInput::Release(code))?;
+                }
+                Ok(())
+            },
+            _ => Ok(())
+        }
+    }
+    pub(crate) fn device_event(&mut self, frame: u64, event: &DeviceEvent) -> Result<(), ThinError> {
+        match event {
+            DeviceEvent::MouseMotion { delta } => {
+                self.record(frame, ScriptedInput::MouseMotion(vec2(delta.0 as f32, delta.1 as f32)))
+            },
+            DeviceEvent::MouseWheel { delta } => self.record(frame, ScriptedInput::Scroll(scroll(delta))),
+            _ => Ok(())
+        }
+    }
+    /// compares the state of every gamepad to the inputs that were held down. run this after
+    /// the input map has read the gilrs events.
+    pub(crate) fn gamepads(&mut self, frame: u64, gilrs: &gilrs::Gilrs, press_sensitivity: f32) -> Result<(), ThinError> {
+        if !self.focused { return Ok(()) }
+        let mut values = [0.0_f32; GAMEPAD_INPUTS.len()];
+        let mut set = |input: GamepadInput, value: f32| {
+            let i = GAMEPAD_INPUTS.iter().position(|i| *i == input).unwrap_or(GAMEPAD_INPUTS.len() - 1);
+            values[i] = values[i].max(value);
+        };
+        for (_, gamepad) in gilrs.gamepads() {
+            for button in BUTTONS {
+                let value = gamepad.button_data(button).map(|i| i.value()).unwrap_or(0.0);
+                set(button.into(), value);
+            }
+            for axis in AXES {
+                let value = gamepad.value(axis);
+                set(axis_pos(axis), value.max(0.0));
+                set(axis_neg(axis), (-value).max(0.0));
+            }
+        }
+        for (input, value) in GAMEPAD_INPUTS.into_iter().zip(values) {
+            self.set_pressed(frame, input.with_sid(SpecifyGamepad::Any), value >= press_sensitivity)?;
+        }
+        Ok(())
+    }
+    pub(crate) fn flush(&mut self) -> Result<(), ThinError> {
+        Ok(self.writer.flush()?)
+    }
+    /// marks the end of the recording.
+    pub(crate) fn finish(&mut self, frame: u64) -> Result<(), ThinError> {
+        write_line(&mut self.writer, &Line::End { frame })?;
+        self.flush()
+    }
+}
+fn scroll(delta: &MouseScrollDelta) -> Vec2 {
+    match delta {
+        MouseScrollDelta::LineDelta(x, y) => vec2(*x, *y),
+        MouseScrollDelta::PixelDelta(pos) => vec2(pos.x as f32, pos.y as f32)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winit::keyboard::KeyCode;
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("thin-engine-{}-{name}.jsonl", std::process::id()))
+    }
+    #[test]
+    fn save_and_load() {
+        let script = InputScript::new()
+            .tap(2, KeyCode::Space)
+            .press(3, MouseButton::Left)
+            .press(4, GamepadInput::South.with_sid(SpecifyGamepad::Any))
+            .with(5, ScriptedInput::MouseMotion(vec2(1.5, -2.0)))
+            .with(5, ScriptedInput::CursorMoved(vec2(10.0, 20.0)))
+            .with(6, ScriptedInput::Scroll(vec2(0.0, 1.0)))
+            .with(7, ScriptedInput::Text("hi".into()))
+            .with_end(8);
+        let path = temp_path("round-trip");
+        script.save(&path).unwrap();
+        let loaded = InputScript::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.inputs(), script.inputs());
+        assert_eq!(loaded.end(), Some(8));
+    }
+    #[test]
+    fn load_error_line() {
+        let path = temp_path("bad-line");
+        let good = serde_json::to_string(&Line::End { frame: 1 }).unwrap();
+        std::fs::write(&path, format!("{good}\n\n{{\"Input\": 3}}\n")).unwrap();
+        let result = InputScript::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        // blank lines are skipped but still counted
+        assert!(matches!(result, Err(ThinError::RecordingError(3, _))));
+    }
+    #[test]
+    fn unknown_gamepad_input() {
+        let path = temp_path("unknown-gamepad");
+        std::fs::write(&path, "{\"Input\":{\"frame\":0,\"input\":{\"Press\":{\"Gamepad\":\"Jump\"}}}}\n").unwrap();
+        let result = InputScript::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(result, Err(ThinError::RecordingError(1, _))));
+    }
+}
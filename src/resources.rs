@@ -0,0 +1,194 @@
+//! A registry of GPU resources that are rebuilt when the display is recreated.
+//!
+//! On some platforms (mainly android) the window and display are dropped when the app is
+//! suspended, invalidating every mesh, program and texture made with them. Resources added to
+//! `settings.resources` are released before the display is dropped and rebuilt with the new
+//! display before `resumed` is run, so handles to them stay valid across a suspend.
+//! ```no_run
+//! use thin_engine::{prelude::*, meshes::teapot};
+//! struct Graphics {
+//!     teapot: Resource<(IndexBuffer<u16>, VertexBuffer<Vertex>, VertexBuffer<Normal>)>,
+//!     program: Resource<Program>
+//! }
+//! let event_loop = EventLoop::new().unwrap();
+//! thin_engine::builder(input_map!()).with_setup(|display, _, _, settings, _| {
+//!     let teapot = settings.resources.add(display, |display| mesh!(
+//!         display, &teapot::INDICES, &teapot::VERTICES, &teapot::NORMALS
+//!     ))?;
+//!     let program = settings.resources.add(display, |display| Program::from_source(
+//!         display, shaders::SCREEN_VERTEX, "#version 140
+//!         out vec4 colour;
+//!         void main() { colour = vec4(1.0); }", None
+//!     ))?;
+//!     Ok(Graphics { teapot, program })
+//! }).with_draw(|graphics, _, display, _, _, window, _| {
+//!     let (indices, vertices, normals) = &*graphics.teapot.get();
+//!     let mut frame = display.draw();
+//!     frame.clear_color(0.0, 0.0, 0.0, 1.0);
+//!     frame.draw(
+//!         (vertices, normals), indices, &*graphics.program.get(),
+//!         &uniform! {}, &DrawParameters::default()
+//!     )?;
+//!     window.pre_present_notify();
+//!     frame.finish()?;
+//!     Ok(())
+//! }).with_fixed_tps(60).build(event_loop).unwrap();
+//! ```
+#![allow(clippy::type_complexity)]
+use crate::{Display, ThinError};
+use std::{rc::Rc, cell::{RefCell, Ref, RefMut}};
+/// a handle to a resource in `Resources`. cloning the handle shares the same resource.
+pub struct Resource<T>(pub(crate) Rc<RefCell<Option<T>>>);
+impl<T> Clone for Resource<T> {
+    fn clone(&self) -> Self { Self(self.0.clone()) }
+}
+impl<T> Resource<T> {
+    /// borrows the resource or panics if the app is suspended. to handle failed borrows use
+    /// `try_get()` instead.
+    pub fn get(&self) -> Ref<'_, T> {
+        self.try_get().expect("resource was released. it can't be used while the app is suspended")
+    }
+    /// mutably borrows the resource or panics if the app is suspended.
+    pub fn get_mut(&self) -> RefMut<'_, T> {
+        RefMut::map(self.0.borrow_mut(), |i| {
+            i.as_mut().expect("resource was released. it can't be used while the app is suspended")
+        })
+    }
+    /// borrows the resource, returning none while the app is suspended.
+    pub fn try_get(&self) -> Option<Ref<'_, T>> {
+        Ref::filter_map(self.0.borrow(), |i| i.as_ref()).ok()
+    }
+}
+/// a resource that the registry knows how to release and rebuild.
+trait Recreatable<D> {
+    fn release(&mut self);
+    fn recreate(&mut self, display: &D) -> Result<(), ThinError>;
+    /// true if every handle to the resource has been dropped.
+    fn is_unused(&self) -> bool;
+}
+/// a resource that is rebuilt by running the function that made it.
+struct Rebuilt<T, D> {
+    value: Rc<RefCell<Option<T>>>,
+    create: Box<dyn FnMut(&D) -> Result<T, ThinError>>
+}
+impl<T, D> Recreatable<D> for Rebuilt<T, D> {
+    fn release(&mut self) { *self.value.borrow_mut() = None }
+    fn recreate(&mut self, display: &D) -> Result<(), ThinError> {
+        *self.value.borrow_mut() = Some((self.create)(display)?);
+        Ok(())
+    }
+    fn is_unused(&self) -> bool { Rc::strong_count(&self.value) == 1 }
+}
+/// a font keeps its glyph data and only drops its textures, which are loaded again when used.
+#[cfg(feature = "text")]
+struct FontEntry(Rc<RefCell<Option<crate::text_renderer::Font>>>);
+#[cfg(feature = "text")]
+impl<D> Recreatable<D> for FontEntry {
+    fn release(&mut self) {
+        if let Some(font) = self.0.borrow_mut().as_mut() { font.clear_loaded() }
+    }
+    fn recreate(&mut self, _display: &D) -> Result<(), ThinError> { Ok(()) }
+    fn is_unused(&self) -> bool { Rc::strong_count(&self.0) == 1 }
+}
+/// GPU resources that thin engine will rebuild after the display is recreated. found in
+/// `Settings::resources`. `D` is what they are made with, a `Display` unless the registry is
+/// used with something else such as a headless context.
+pub struct Resources<D = Display> {
+    entries: Vec<Box<dyn Recreatable<D>>>
+}
+impl<D> Default for Resources<D> {
+    fn default() -> Self { Self { entries: Vec::new() } }
+}
+impl<D: 'static> Resources<D> {
+    /// runs `create` now and again whenever the display is recreated, returning a handle to
+    /// the result.
+    pub fn add<T: 'static, E>(
+        &mut self, display: &D,
+        mut create: impl FnMut(&D) -> Result<T, E> + 'static
+    ) -> Result<Resource<T>, ThinError> where ThinError: From<E> {
+        let value = Rc::new(RefCell::new(Some(create(display)?)));
+        let create = Box::new(move |display: &D| Ok(create(display)?));
+        self.entries.push(Box::new(Rebuilt { value: value.clone(), create }));
+        Ok(Resource(value))
+    }
+    /// adds a font whose glyph textures are dropped on suspend and loaded again when next used.
+    #[cfg(feature = "text")]
+    pub fn add_font(&mut self, font: crate::text_renderer::Font) -> Resource<crate::text_renderer::Font> {
+        let value = Rc::new(RefCell::new(Some(font)));
+        self.entries.push(Box::new(FontEntry(value.clone())));
+        Resource(value)
+    }
+    /// the amount of resources in the registry.
+    pub fn len(&self) -> usize { self.entries.len() }
+    /// returns true if there are no resources in the registry.
+    pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+    /// drops resources that no handles point to.
+    pub fn remove_unused(&mut self) {
+        self.entries.retain(|i| !i.is_unused());
+    }
+    /// drops every resource so that the display can be dropped.
+    pub(crate) fn release(&mut self) {
+        self.remove_unused();
+        for entry in &mut self.entries { entry.release() }
+    }
+    /// rebuilds every resource with a new display.
+    pub(crate) fn recreate(&mut self, display: &D) -> Result<(), ThinError> {
+        self.remove_unused();
+        for entry in &mut self.entries { entry.recreate(display)? }
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn resources_are_rebuilt_after_release() {
+        let mut resources = Resources::<u32>::default();
+        let built = Rc::new(RefCell::new(0));
+        let counter = built.clone();
+        let resource = resources.add(&1, move |display: &u32| {
+            *counter.borrow_mut() += 1;
+            Ok::<_, ThinError>(*display * 10)
+        }).unwrap();
+        assert_eq!(*resource.get(), 10);
+
+        resources.release();
+        assert!(resource.try_get().is_none());
+        resources.recreate(&2).unwrap();
+        assert_eq!(*resource.get(), 20);
+        assert_eq!(*built.borrow(), 2);
+
+        *resource.get_mut() += 1;
+        assert_eq!(*resource.clone().get(), 21);
+    }
+    #[test]
+    fn unused_resources_are_removed() {
+        let mut resources = Resources::<()>::default();
+        let kept = resources.add(&(), |_| Ok::<_, ThinError>("kept")).unwrap();
+        let dropped = resources.add(&(), |_| Ok::<_, ThinError>("dropped")).unwrap();
+        let shared = dropped.clone();
+        drop(dropped);
+        resources.remove_unused();
+        assert_eq!(resources.len(), 2);
+
+        drop(shared);
+        resources.remove_unused();
+        assert_eq!(resources.len(), 1);
+        // released and recreated resources stay unused once dropped
+        resources.release();
+        drop(kept);
+        resources.recreate(&()).unwrap();
+        assert!(resources.is_empty());
+    }
+    #[test]
+    fn failed_recreate_is_an_error() {
+        let mut resources = Resources::<bool>::default();
+        let resource = resources.add(&true, |ok: &bool| match ok {
+            true => Ok(()),
+            false => Err(ThinError::Other("failed".into()))
+        }).unwrap();
+        resources.release();
+        assert!(resources.recreate(&false).is_err());
+        assert!(resource.try_get().is_none());
+    }
+}
@@ -0,0 +1,275 @@
+//! A stack of scenes for splitting a game into units like a title screen, a level and a pause
+//! menu. Only the top scene is updated but scenes below it can keep drawing for overlays.
+//! Without a fixed timestep the stack requests a redraw after every update so scenes are drawn
+//! each frame either way.
+//! ```no_run
+//! use thin_engine::prelude::*;
+//! struct Level { time: f32 }
+//! struct Pause;
+//! impl Scene<KeyCode> for Level {
+//!     fn update(
+//!         &mut self, input: &mut InputMap<KeyCode>, _display: &Display, _settings: &mut Settings,
+//!         _event_loop: &ActiveEventLoop, _window: &mut Window, time: &Time
+//!     ) -> Result<Transition<KeyCode>, ThinError> {
+//!         self.time += time.delta_secs();
+//!         if input.pressed(KeyCode::Escape) { return Ok(Transition::push(Pause)) }
+//!         Ok(Transition::None)
+//!     }
+//!     fn draw(
+//!         &mut self, frame: &mut Frame, _input: &mut InputMap<KeyCode>, _display: &Display,
+//!         _settings: &mut Settings, _event_loop: &ActiveEventLoop, _window: &mut Window, _time: &Time
+//!     ) -> Result<(), ThinError> {
+//!         frame.clear_color(self.time.sin() * 0.5 + 0.5, 0.3, 0.6, 1.0);
+//!         Ok(())
+//!     }
+//! }
+//! impl Scene<KeyCode> for Pause {
+//!     fn update(
+//!         &mut self, input: &mut InputMap<KeyCode>, _display: &Display, _settings: &mut Settings,
+//!         _event_loop: &ActiveEventLoop, _window: &mut Window, _time: &Time
+//!     ) -> Result<Transition<KeyCode>, ThinError> {
+//!         if input.pressed(KeyCode::Escape) { return Ok(Transition::Pop) }
+//!         Ok(Transition::None)
+//!     }
+//!     // the level stays visible under the pause menu
+//!     fn draw_below(&self) -> bool { true }
+//! }
+//! let event_loop = EventLoop::new().unwrap();
+//! let input = input_map!((KeyCode::Escape, KeyCode::Escape));
+//! thin_engine::builder(input)
+//!     .build_app(event_loop, &mut SceneStack::new(Level { time: 0.0 })).unwrap();
+//! ```
+#![allow(clippy::too_many_arguments)]
+use crate::prelude::*;
+use winit::event::Event;
+use std::hash::Hash;
+/// a unit of the game such as a menu or level. all methods have empty default implementations.
+#[allow(unused_variables)]
+pub trait Scene<H: Hash + PartialEq + Eq + Clone + Copy> {
+    /// this is run when the scene is added to the stack.
+    fn enter(&mut self, display: &Display, window: &mut Window, event_loop: &ActiveEventLoop) -> Result<(), ThinError> {
+        Ok(())
+    }
+    /// this is run when the scene is removed from the stack, including when the program exits.
+    fn exit(&mut self, event_loop: &ActiveEventLoop) -> Result<(), ThinError> { Ok(()) }
+    /// this is run when the scene above this one is popped, making this the top scene again.
+    fn uncovered(&mut self, event_loop: &ActiveEventLoop) -> Result<(), ThinError> { Ok(()) }
+    /// this is run for the top scene only. the returned transition is applied straight after.
+    fn update(
+        &mut self, input: &mut InputMap<H>, display: &Display, settings: &mut Settings,
+        event_loop: &ActiveEventLoop, window: &mut Window, time: &Time
+    ) -> Result<Transition<H>, ThinError> { Ok(Transition::None) }
+    /// draws the scene to the shared frame. scenes are drawn from the bottom up so overlays are
+    /// drawn over the scenes below them.
+    fn draw(
+        &mut self, frame: &mut Frame, input: &mut InputMap<H>, display: &Display,
+        settings: &mut Settings, event_loop: &ActiveEventLoop, window: &mut Window, time: &Time
+    ) -> Result<(), ThinError> { Ok(()) }
+    /// recieves events for the top scene only.
+    fn event(
+        &mut self, event: Event<()>, settings: &mut Settings, event_loop: &ActiveEventLoop,
+        state: Option<&(Window, Display)>
+    ) -> Result<(), ThinError> { Ok(()) }
+    /// if true the scene below this one is drawn first, useful for pause menus and overlays.
+    fn draw_below(&self) -> bool { false }
+}
+/// a change to the scene stack returned from `Scene::update()`.
+pub enum Transition<H: Hash + PartialEq + Eq + Clone + Copy> {
+    /// keep the current scene.
+    None,
+    /// add a scene on top of the current one, pausing it.
+    Push(Box<dyn Scene<H>>),
+    /// remove the current scene, exits the program if it was the last one.
+    Pop,
+    /// swap the current scene for a new one.
+    Replace(Box<dyn Scene<H>>),
+    /// remove every scene and exit the program.
+    Exit
+}
+impl<H: Hash + PartialEq + Eq + Clone + Copy> Transition<H> {
+    /// boxes a scene to push it on top of the current one.
+    pub fn push(scene: impl Scene<H> + 'static) -> Self {
+        Self::Push(Box::new(scene))
+    }
+    /// boxes a scene to replace the current one.
+    pub fn replace(scene: impl Scene<H> + 'static) -> Self {
+        Self::Replace(Box::new(scene))
+    }
+}
+/// a `ThinApp` that runs a stack of scenes. run it with `ThinBuilder::build_app()`.
+pub struct SceneStack<H: Hash + PartialEq + Eq + Clone + Copy> {
+    scenes: Vec<Box<dyn Scene<H>>>,
+}
+impl<H: Hash + PartialEq + Eq + Clone + Copy> SceneStack<H> {
+    /// creates a stack with a starting scene.
+    pub fn new(scene: impl Scene<H> + 'static) -> Self {
+        Self { scenes: vec![Box::new(scene)] }
+    }
+    /// the amount of scenes in the stack.
+    pub fn len(&self) -> usize { self.scenes.len() }
+    /// returns true if there are no scenes left.
+    pub fn is_empty(&self) -> bool { self.scenes.is_empty() }
+    /// applies a transition to the stack, running the enter and exit hooks of the scenes.
+    pub fn apply(
+        &mut self, transition: Transition<H>, display: &Display,
+        window: &mut Window, event_loop: &ActiveEventLoop
+    ) -> Result<(), ThinError> {
+        let exit = self.transition(transition, |hook, scene| match hook {
+            Hook::Enter => scene.enter(display, window, event_loop),
+            Hook::Exit => scene.exit(event_loop),
+            Hook::Uncovered => scene.uncovered(event_loop)
+        })?;
+        if exit { event_loop.exit() }
+        Ok(())
+    }
+    /// applies a transition, running `hook` on the scenes it changes in order. returns true if
+    /// the program should exit.
+    fn transition(
+        &mut self, transition: Transition<H>,
+        mut hook: impl FnMut(Hook, &mut dyn Scene<H>) -> Result<(), ThinError>
+    ) -> Result<bool, ThinError> {
+        match transition {
+            Transition::None => (),
+            Transition::Push(mut scene) => {
+                hook(Hook::Enter, scene.as_mut())?;
+                self.scenes.push(scene);
+            },
+            Transition::Pop => {
+                if let Some(mut scene) = self.scenes.pop() { hook(Hook::Exit, scene.as_mut())? }
+                match self.scenes.last_mut() {
+                    Some(scene) => hook(Hook::Uncovered, scene.as_mut())?,
+                    None => return Ok(true)
+                }
+            },
+            Transition::Replace(mut scene) => {
+                if let Some(mut old) = self.scenes.pop() { hook(Hook::Exit, old.as_mut())? }
+                hook(Hook::Enter, scene.as_mut())?;
+                self.scenes.push(scene);
+            },
+            Transition::Exit => {
+                while let Some(mut scene) = self.scenes.pop() { hook(Hook::Exit, scene.as_mut())? }
+                return Ok(true)
+            }
+        }
+        Ok(false)
+    }
+    /// the index of the lowest scene that is drawn.
+    fn lowest_visible(&self) -> usize {
+        let mut start = self.scenes.len().saturating_sub(1);
+        while start > 0 && self.scenes[start].draw_below() { start -= 1 }
+        start
+    }
+}
+/// a hook of a scene run by a transition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Hook { Enter, Exit, Uncovered }
+impl<H: Hash + PartialEq + Eq + Clone + Copy, E: 'static> ThinApp<H, E> for SceneStack<H> {
+    fn setup(
+        &mut self, display: &Display, window: &mut Window, event_loop: &ActiveEventLoop,
+        _settings: &mut Settings, _proxy: &EventLoopProxy<E>
+    ) -> Result<(), ThinError> {
+        // the first scene is entered here as it needs the display
+        match self.scenes.last_mut() {
+            Some(scene) => scene.enter(display, window, event_loop),
+            None => Ok(())
+        }
+    }
+    fn update(
+        &mut self, input: &mut InputMap<H>, display: &Display, settings: &mut Settings,
+        event_loop: &ActiveEventLoop, window: &mut Window, time: &Time
+    ) -> Result<(), ThinError> {
+        let Some(scene) = self.scenes.last_mut() else { return Ok(()) };
+        let transition = scene.update(input, display, settings, event_loop, window, time)?;
+        // scenes only render in draw, which thin engine only requests with a fixed timestep
+        if settings.fixed_timestep.is_none() { window.request_redraw() }
+        self.apply(transition, display, window, event_loop)
+    }
+    fn draw(
+        &mut self, input: &mut InputMap<H>, display: &Display, settings: &mut Settings,
+        event_loop: &ActiveEventLoop, window: &mut Window, time: &Time
+    ) -> Result<(), ThinError> {
+        if self.scenes.is_empty() { return Ok(()) }
+        let start = self.lowest_visible();
+
+        let mut frame = display.draw();
+        let result = self.scenes[start..].iter_mut().try_for_each(|scene| {
+            scene.draw(&mut frame, input, display, settings, event_loop, window, time)
+        });
+        // the frame has to be finished even if a scene failed to draw
+        window.pre_present_notify();
+        frame.finish()?;
+        result
+    }
+    fn event(
+        &mut self, event: Event<()>, settings: &mut Settings, event_loop: &ActiveEventLoop,
+        state: Option<&(Window, Display)>
+    ) -> Result<(), ThinError> {
+        let Some(scene) = self.scenes.last_mut() else { return Ok(()) };
+        scene.event(event, settings, event_loop, state)
+    }
+    fn exit(&mut self, event_loop: &ActiveEventLoop) -> Result<(), ThinError> {
+        while let Some(mut scene) = self.scenes.pop() { scene.exit(event_loop)? }
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    /// a scene that only says if it draws below.
+    struct Overlay(bool);
+    impl Scene<u8> for Overlay {
+        fn draw_below(&self) -> bool { self.0 }
+    }
+    fn id(scene: &dyn Scene<u8>) -> *const () { scene as *const dyn Scene<u8> as *const () }
+    /// applies a transition, returning the hooks it ran and if it exits.
+    fn run(stack: &mut SceneStack<u8>, transition: Transition<u8>) -> (Vec<(Hook, *const ())>, bool) {
+        let mut hooks = Vec::new();
+        let exit = stack.transition(transition, |hook, scene| { hooks.push((hook, id(scene))); Ok(()) }).unwrap();
+        (hooks, exit)
+    }
+    #[test]
+    fn transitions_run_hooks_in_order() {
+        let mut stack = SceneStack::new(Overlay(false));
+        let a = id(stack.scenes[0].as_ref());
+        let (b, c): (Box<dyn Scene<u8>>, Box<dyn Scene<u8>>) = (Box::new(Overlay(true)), Box::new(Overlay(false)));
+        let (b_id, c_id) = (id(b.as_ref()), id(c.as_ref()));
+
+        assert_eq!(run(&mut stack, Transition::None), (vec![], false));
+        assert_eq!(run(&mut stack, Transition::Push(b)), (vec![(Hook::Enter, b_id)], false));
+        assert_eq!(stack.len(), 2);
+        assert_eq!(run(&mut stack, Transition::Replace(c)), (vec![(Hook::Exit, b_id), (Hook::Enter, c_id)], false));
+        assert_eq!(stack.len(), 2);
+        assert_eq!(run(&mut stack, Transition::Pop), (vec![(Hook::Exit, c_id), (Hook::Uncovered, a)], false));
+        assert_eq!(run(&mut stack, Transition::Pop), (vec![(Hook::Exit, a)], true));
+        assert!(stack.is_empty());
+    }
+    #[test]
+    fn exit_removes_every_scene_from_the_top() {
+        let mut stack = SceneStack::new(Overlay(false));
+        let a = id(stack.scenes[0].as_ref());
+        let b: Box<dyn Scene<u8>> = Box::new(Overlay(false));
+        let b_id = id(b.as_ref());
+        run(&mut stack, Transition::Push(b));
+        assert_eq!(run(&mut stack, Transition::Exit), (vec![(Hook::Exit, b_id), (Hook::Exit, a)], true));
+        assert!(stack.is_empty());
+    }
+    #[test]
+    fn failed_enter_does_not_push() {
+        let mut stack = SceneStack::new(Overlay(false));
+        let result = stack.transition(Transition::push(Overlay(false)), |_, _| Err(ThinError::Other("no".into())));
+        assert!(result.is_err());
+        assert_eq!(stack.len(), 1);
+    }
+    #[test]
+    fn scenes_below_overlays_are_drawn() {
+        let mut stack = SceneStack::new(Overlay(false));
+        assert_eq!(stack.lowest_visible(), 0);
+        run(&mut stack, Transition::push(Overlay(false)));
+        run(&mut stack, Transition::push(Overlay(true)));
+        assert_eq!(stack.lowest_visible(), 1);
+        run(&mut stack, Transition::push(Overlay(true)));
+        assert_eq!(stack.lowest_visible(), 1);
+        run(&mut stack, Transition::push(Overlay(false)));
+        assert_eq!(stack.lowest_visible(), 4);
+    }
+}
@@ -0,0 +1,159 @@
+//! Scripted input for replays and headless runs. A script is a list of inputs and the frames
+//! they happen on. Keyboard and gamepad events can't be made outside of winit and gilrs, so
+//! the input map is rebound to send scripted presses through unused mouse buttons instead.
+use winit::{event::{WindowEvent, DeviceEvent, DeviceId, ElementState, MouseButton, MouseScrollDelta}, dpi::PhysicalPosition};
+use winit_input_map::{InputMap, InputCode, DeviceInput, SpecifyDevice};
+use glium_types::vectors::Vec2;
+use std::{hash::Hash, collections::HashMap};
+/// an input sent to the input map on a certain frame of a script.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptedInput {
+    /// presses a key, mouse button or gamepad button.
+    Press(InputCode),
+    /// releases a key, mouse button or gamepad button.
+    Release(InputCode),
+    /// moves the mouse by a delta, like `DeviceEvent::MouseMotion`
+    MouseMotion(Vec2),
+    /// sets the cursor position in pixels.
+    CursorMoved(Vec2),
+    /// scrolls by a number of lines.
+    Scroll(Vec2),
+    /// text typed this frame.
+    Text(String)
+}
+/// a list of inputs and the frames they happen on, sent to the input map before update.
+#[derive(Debug, Clone, Default)]
+pub struct InputScript {
+    inputs: Vec<(u64, ScriptedInput)>,
+    end: Option<u64>
+}
+impl InputScript {
+    pub fn new() -> Self { Self::default() }
+    /// adds an input on `frame`.
+    pub fn push(&mut self, frame: u64, input: ScriptedInput) {
+        self.inputs.push((frame, input));
+    }
+    /// adds an input on `frame`.
+    pub fn with(mut self, frame: u64, input: ScriptedInput) -> Self {
+        self.push(frame, input);
+        self
+    }
+    pub fn press(self, frame: u64, code: impl Into<InputCode>) -> Self {
+        self.with(frame, ScriptedInput::Press(code.into()))
+    }
+    pub fn release(self, frame: u64, code: impl Into<InputCode>) -> Self {
+        self.with(frame, ScriptedInput::Release(code.into()))
+    }
+    /// presses on `frame` and releases `frames` later.
+    pub fn hold(self, frame: u64, frames: u64, code: impl Into<InputCode>) -> Self {
+        let code = code.into();
+        self.press(frame, code).release(frame + frames.max(1), code)
+    }
+    /// presses on `frame` and releases on the next frame.
+    pub fn tap(self, frame: u64, code: impl Into<InputCode>) -> Self {
+        self.hold(frame, 1, code)
+    }
+    /// the inputs that happen on `frame` in the order they were added.
+    pub fn inputs_at(&self, frame: u64) -> impl Iterator<Item = &ScriptedInput> {
+        self.inputs.iter().filter(move |(i, _)| *i == frame).map(|(_, input)| input)
+    }
+    /// every input and the frame it happens on.
+    pub fn inputs(&self) -> &[(u64, ScriptedInput)] { &self.inputs }
+    /// sets the frame the script ends on. a replay exits when it reaches the end.
+    pub fn set_end(&mut self, frame: Option<u64>) { self.end = frame }
+    pub fn with_end(mut self, frame: u64) -> Self {
+        self.end = Some(frame);
+        self
+    }
+    /// the frame the script ends on, if it has one.
+    pub fn end(&self) -> Option<u64> { self.end }
+    /// sorts the inputs by frame, keeping the order of inputs on the same frame.
+    pub(crate) fn sort(&mut self) { self.inputs.sort_by_key(|(frame, _)| *frame) }
+    /// the amount of inputs in the script.
+    pub fn len(&self) -> usize { self.inputs.len() }
+    /// returns true if there are no inputs in the script.
+    pub fn is_empty(&self) -> bool { self.inputs.is_empty() }
+}
+/// sends scripted inputs to an input map. key and gamepad events can't be made outside of
+/// winit and gilrs, so every bound input is rebound to an unused mouse button which is pressed
+/// in its place.
+pub(crate) struct ScriptedInputMap {
+    buttons: HashMap<InputCode, MouseButton>
+}
+impl ScriptedInputMap {
+    /// rebinds `input` so that it can be scripted. the actions of the input map are unchanged.
+    pub(crate) fn new<H: Hash + PartialEq + Eq + Clone + Copy>(input: &mut InputMap<H>) -> Self {
+        let mut buttons = HashMap::new();
+        let mut binds = input.get_binds();
+        for code in binds.iter_mut().flat_map(|(_, binds)| binds).flatten() {
+            let any = code.set_any();
+            let button = match any {
+                InputCode::Device { input: DeviceInput::Button(button), .. } => button,
+                // mouse movement and scrolling can be sent as is
+                InputCode::Device { input: DeviceInput::Key(_), .. } | InputCode::Gamepad { .. } => {
+                    let next = MouseButton::Other(u16::MAX - buttons.len() as u16);
+                    *buttons.entry(any).or_insert(next)
+                },
+                _ => continue
+            };
+            buttons.insert(any, button);
+            *code = DeviceInput::Button(button).with_sid(SpecifyDevice::Any);
+        }
+        let mut scripted = InputMap::new(&binds);
+        scripted.press_sensitivity = input.press_sensitivity;
+        scripted.mouse_scale = input.mouse_scale;
+        scripted.scroll_scale = input.scroll_scale;
+        *input = scripted;
+        Self { buttons }
+    }
+    pub(crate) fn send<H: Hash + PartialEq + Eq + Clone + Copy>(&self, input: &mut InputMap<H>, scripted: &ScriptedInput) {
+        let device_id = DeviceId::dummy();
+        let button = |code: &InputCode| self.buttons.get(&code.set_any()).copied();
+        match scripted {
+            ScriptedInput::Press(code) | ScriptedInput::Release(code) => {
+                let Some(button) = button(code) else { return };
+                let state = if let ScriptedInput::Press(_) = scripted { ElementState::Pressed }
+                    else { ElementState::Released };
+                input.update_with_window_event(&WindowEvent::MouseInput { device_id, state, button });
+            },
+            ScriptedInput::MouseMotion(delta) => input.update_with_device_event(device_id, &DeviceEvent::MouseMotion {
+                delta: (delta.x as f64, delta.y as f64)
+            }),
+            ScriptedInput::CursorMoved(pos) => input.update_with_window_event(&WindowEvent::CursorMoved {
+                device_id, position: PhysicalPosition::new(pos.x as f64, pos.y as f64)
+            }),
+            ScriptedInput::Scroll(delta) => input.update_with_device_event(device_id, &DeviceEvent::MouseWheel {
+                delta: MouseScrollDelta::LineDelta(delta.x, delta.y)
+            }),
+            ScriptedInput::Text(text) => match &mut input.text_typed {
+                Some(typed) => typed.push_str(text),
+                None => input.text_typed = Some(text.clone())
+            }
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winit::keyboard::KeyCode;
+    use winit_input_map::input_map;
+    #[test]
+    fn tap_and_hold() {
+        let script = InputScript::new().tap(1, KeyCode::Space).hold(2, 3, KeyCode::KeyA);
+        let frames: Vec<_> = script.inputs().iter().map(|(i, _)| *i).collect();
+        assert_eq!(frames, [1, 2, 2, 5]);
+        assert_eq!(script.inputs_at(2).count(), 2);
+    }
+    #[test]
+    fn scripted_keys_press_actions() {
+        let mut input = input_map!((0, KeyCode::Space), (1, KeyCode::KeyA));
+        let scripted = ScriptedInputMap::new(&mut input);
+        scripted.send(&mut input, &ScriptedInput::Press(KeyCode::Space.into()));
+        assert!(input.pressed(0));
+        assert!(!input.pressed(1));
+
+        input.init();
+        scripted.send(&mut input, &ScriptedInput::Release(KeyCode::Space.into()));
+        assert!(!input.pressing(0));
+    }
+}
@@ -1,5 +1,4 @@
-use glium::{uniform, uniforms::*, Program, program::ProgramCreationError, texture::*};
-use crate::Display;
+use glium::{uniform, uniforms::*, Program, program::ProgramCreationError, texture::*, backend::Facade};
 use glium_types::prelude::*;
 pub use glium_types::shaders::VERTEX;
 
@@ -126,7 +125,7 @@ void main() {
 }";
 /// A shader for smoothing jaggerd pixel edges. Use with `fxaa_uniforms` with the input of a
 /// texture. (check simple-fxaa example)
-pub fn fxaa_shader(display: &Display) -> Result<Program, ProgramCreationError> {
+pub fn fxaa_shader(display: &impl Facade) -> Result<Program, ProgramCreationError> {
     Program::from_source(display, SCREEN_VERTEX, FXAA, None)
 }
 /// Takes the render results texture and gives the appropiate fxaa uniform values. Use with the fxaa shader.
@@ -1,7 +1,6 @@
 //! A module for font renderering. 
 //! ```
 //! use thin_engine::{text_renderer::*, prelude::*};
-//! use std::{cell::RefCell, rc::Rc};
 //!     let event_loop = EventLoop::new().unwrap();
 //!     event_loop.set_control_flow(ControlFlow::Poll);
 //! 
@@ -11,9 +10,6 @@
 //!         vertices: VertexBuffer<Vertex>,
 //!         uvs: VertexBuffer<TextureCoords>
 //!     }
-//!     let graphics: Rc<RefCell<Option<Graphics>>> = Rc::default();
-//!     let graphics_setup = graphics.clone();
-//! 
 //!     let mut font = Font::from_scale_and_file(40.0, "examples/DroidSans.ttf").unwrap();
 //!     let draw_params = DrawParameters {
 //!         blend: glium::Blend::alpha_blending(),
@@ -21,16 +17,13 @@
 //!         ..params::alias_3d()
 //!     };
 //! 
-//!     let time = std::time::Instant::now();
-//! 
 //!     let text = "Text can be drawn in 2d or 3d thanks to the power of Matrices. Text is drawn without wrapping and tab spacing, however the font struct has a function to format text for you.";
-//!     thin_engine::builder(input_map!()).with_setup(|display, _window, _event_loop| {
-//!         let (indices, vertices, uvs) = Font::mesh(display).unwrap();
-//!         let shader = Font::shader(display).unwrap();
-//!         graphics_setup.replace(Some(Graphics { indices, vertices, uvs, shader }));
-//!     }).with_update(|_input, display, _settings, _target, window| {
-//!         let graphics = graphics.borrow();
-//!         let Graphics { shader, vertices, uvs, indices } = graphics.as_ref().unwrap();
+//!     thin_engine::builder(input_map!()).with_setup(|display, _window, _event_loop, _settings, _proxy| {
+//!         let (indices, vertices, uvs) = Font::mesh(display)?;
+//!         let shader = Font::shader(display)?;
+//!         Ok(Graphics { indices, vertices, uvs, shader })
+//!     }).with_update(|graphics, _input, display, _settings, _target, window, time| {
+//!         let Graphics { shader, vertices, uvs, indices } = graphics;
 //! 
 //!         let text_renderer = TextRenderer {
 //!             shader, indices, vertices, uvs, draw_params: &draw_params, display
@@ -51,7 +44,7 @@
 //!         let formated_text = font.format_text(text, Some(width as f32/font_size), 8, display);
 //!         let formated_3d_text = font.format_text(text, Some(10.0), 8, display);
 //!         let pos = vec3(-(width as f32 / height as f32), 1.0, 0.0);
-//!         let time = time.elapsed().as_secs_f32();
+//!         let time = time.elapsed_secs();
 //! 
 //!         // 3d text
 //!         text_renderer.draw(
@@ -66,27 +59,210 @@
 //!             ) * Mat4::from_pos(vec3(-5.0, 5.0, 0.0)),
 //!             perspective_3d, Mat4::default(),
 //!             &mut font
-//!         ).unwrap();
+//!         )?;
 //! 
 //!         // 2d text
 //!         text_renderer.draw(
 //!             &formated_text, Vec3::ZERO, &mut frame,
 //!             Mat4::from_pos_and_scale(pos, Vec3::splat(0.1)),
 //!             perspective_2d, Mat4::default(), &mut font
-//!         ).unwrap();
+//!         )?;
 //! 
-//!         frame.finish().unwrap();
+//!         frame.finish()?;
+//!         Ok(())
 //!     }).build(event_loop).unwrap();
 //! ```
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
 use crate::prelude::*;
 use std::{fs::*, path::Path, borrow::Cow, ops::Deref, collections::HashMap};
-use glium::{backend::Facade, texture::{RawImage2d, ClientFormat}, uniforms::SamplerWrapFunction};
+use glium::{
+    backend::Facade, index::PrimitiveType, texture::{RawImage2d, ClientFormat, TextureCreationError},
+    uniforms::SamplerWrapFunction
+};
+use glium_types::vert_types::MeshError;
+use atlas::{GlyphAtlas, AtlasGlyph};
 pub use fontdue::{FontSettings, Metrics, OutlineBounds, LineMetrics};
+pub mod atlas;
 /// a struct that represents a font and stores data for drawing. you can write your own renderer
 /// using this struct but it is recommended to use the `TextRenderer` instead.
 pub struct Font {
     scale: f32, font: fontdue::Font,
-    textures: HashMap<char, (Metrics, Option<Texture2d>)>,
+    atlas: GlyphAtlas,
+    kerning: bool,
+    /// the font file and face index for shaping text, if rustybuzz can parse it.
+    #[cfg(feature = "shaping")]
+    face: Option<(Vec<u8>, u32)>,
+    #[cfg(feature = "shaping")]
+    shaping: bool
+}
+/// a glyph in a line of text. sizes are in pixels.
+struct ShapedGlyph { glyph: u16, cluster: usize, offset: Vec2, advance: f32 }
+/// The size and position of text, as returned by `Font::layout()`. positions are in the same
+/// units `TextRenderer` draws in, where the font's scale is 1 unit, with the top left of the
+/// text at (0, 0) unless it is aligned and y going up.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLayout {
+    /// the width of the widest line and the height from the top of the first line to the
+    /// bottom of the last.
+    pub size: Vec2,
+    /// the bottom left of the area the glyphs are drawn in.
+    pub ink_min: Vec2,
+    /// the top right of the area the glyphs are drawn in.
+    pub ink_max: Vec2,
+    pub lines: Vec<LineLayout>,
+    pub glyphs: Vec<GlyphLayout>,
+    /// where a text cursor goes before each character and at the end of each line, in order.
+    pub carets: Vec<Caret>
+}
+/// a line of text in a `TextLayout`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineLayout {
+    /// the byte range of the line in the text, without the new line.
+    pub text: std::ops::Range<usize>,
+    /// the range of the line's glyphs in `TextLayout::glyphs`.
+    pub glyphs: std::ops::Range<usize>,
+    pub width: f32,
+    /// the y position of the line's baseline.
+    pub baseline: f32
+}
+/// a glyph in a `TextLayout`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlyphLayout {
+    /// the index of the glyph in the font.
+    pub glyph: u16,
+    /// the byte index of the first character that made the glyph.
+    pub index: usize,
+    pub line: usize,
+    /// where the glyph starts on the baseline.
+    pub position: Vec2,
+    /// the bottom left of the glyph's image.
+    pub min: Vec2,
+    /// the size of the glyph's image.
+    pub size: Vec2,
+    /// the distance to the next glyph.
+    pub advance: f32
+}
+/// a place a text cursor can be in a `TextLayout`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Caret {
+    /// the byte index of the character after the caret.
+    pub index: usize,
+    pub line: usize,
+    /// the position of the caret on the baseline.
+    pub position: Vec2
+}
+impl TextLayout {
+    /// gets the caret before the character at a byte index, or the end of the line if it is
+    /// a new line.
+    pub fn caret(&self, index: usize) -> Option<Caret> {
+        self.carets.iter().find(|i| i.index >= index).copied()
+    }
+    /// gets the caret closest to a point, e.g. for clicking on text.
+    pub fn closest_caret(&self, point: Vec2) -> Option<Caret> {
+        let line = self.lines.iter().enumerate()
+            .min_by(|(_, a), (_, b)| (a.baseline - point.y).abs().total_cmp(&(b.baseline - point.y).abs()))?.0;
+        self.carets.iter().filter(|i| i.line == line)
+            .min_by(|a, b| (a.position.x - point.x).abs().total_cmp(&(b.position.x - point.x).abs()))
+            .copied()
+    }
+    /// Moves the text so that it is aligned in a box with its top left at (0, 0) that is
+    /// `bounds` wide and tall, going right and down. with a size of zero the text is aligned
+    /// around (0, 0) instead. justified lines are stretched to the width of the box by
+    /// widening their spaces, except for the last line. text isn't wrapped, to wrap it to the
+    /// box use `Font::format_text()` with a wrap of `bounds.x` first.
+    pub fn align(&mut self, text: &str, bounds: Vec2, align: TextAlign) {
+        let y = match align.vertical {
+            VerticalAlign::Top => 0.0,
+            VerticalAlign::Middle => (self.size.y - bounds.y) / 2.0,
+            VerticalAlign::Bottom => self.size.y - bounds.y,
+            VerticalAlign::Baseline => -self.lines.first().map_or(0.0, |i| i.baseline)
+        };
+        let last = self.lines.len().saturating_sub(1);
+        for (i, line) in self.lines.iter_mut().enumerate() {
+            let mut extra = bounds.x - line.width;
+            // the byte indices of the spaces that are widened
+            let mut spaces = Vec::new();
+            let x = match align.horizontal {
+                HorizontalAlign::Left => 0.0,
+                HorizontalAlign::Centre => extra / 2.0,
+                HorizontalAlign::Right => extra,
+                HorizontalAlign::Justified => {
+                    // trailing whitespace is left past the edge so the last word ends on it
+                    let trimmed = text[line.text.clone()].trim_end();
+                    let end = line.text.start + trimmed.len();
+                    let width = self.glyphs[line.glyphs.clone()].iter().filter(|i| i.index < end)
+                        .fold(0.0, |width, i| (i.position.x + i.advance).max(width));
+                    extra = bounds.x - width;
+                    if i != last && extra > 0.0 {
+                        spaces = trimmed.char_indices()
+                            .filter(|(_, c)| c.is_whitespace())
+                            .map(|(i, _)| line.text.start + i).collect();
+                    }
+                    0.0
+                }
+            };
+            let per_space = if spaces.is_empty() { 0.0 } else { extra / spaces.len() as f32 };
+            let offset = |index: usize| vec2(x + per_space * spaces.partition_point(|i| *i < index) as f32, y);
+            for glyph in &mut self.glyphs[line.glyphs.clone()] {
+                let offset = offset(glyph.index);
+                glyph.position += offset;
+                glyph.min += offset;
+            }
+            for caret in self.carets.iter_mut().filter(|c| c.line == i) {
+                caret.position += offset(caret.index);
+            }
+            if !spaces.is_empty() { line.width = bounds.x }
+            line.baseline += y;
+        }
+        self.size.x = self.lines.iter().fold(0.0, |width, i| i.width.max(width));
+        self.measure_ink();
+    }
+    /// finds the area the glyphs are drawn in.
+    fn measure_ink(&mut self) {
+        let mut visible = self.glyphs.iter().filter(|i| i.size.x > 0.0 && i.size.y > 0.0);
+        let Some(first) = visible.next() else { return };
+        let (min, max) = visible.fold((first.min, first.min + first.size), |(min, max), i| (
+            vec2(min.x.min(i.min.x), min.y.min(i.min.y)),
+            vec2(max.x.max(i.min.x + i.size.x), max.y.max(i.min.y + i.size.y))
+        ));
+        (self.ink_min, self.ink_max) = (min, max);
+    }
+}
+/// how text is placed horizontally in its box.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HorizontalAlign {
+    #[default]
+    Left,
+    Centre,
+    Right,
+    /// lines are stretched to fill the width of the box, except for the last line.
+    Justified
+}
+/// how text is placed vertically in its box.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VerticalAlign {
+    /// the top of the first line is at the top of the box.
+    #[default]
+    Top,
+    Middle,
+    /// the baseline of the first line is at the top of the box, e.g. to line up with other
+    /// text.
+    Baseline,
+    /// the bottom of the last line is at the bottom of the box.
+    Bottom
+}
+/// how text is aligned in a box. see `TextLayout::align()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TextAlign {
+    pub horizontal: HorizontalAlign,
+    pub vertical: VerticalAlign
+}
+impl TextAlign {
+    pub fn new(horizontal: HorizontalAlign, vertical: VerticalAlign) -> Self {
+        Self { horizontal, vertical }
+    }
+    /// centred horizontally and vertically, e.g. for buttons.
+    pub fn centred() -> Self { Self::new(HorizontalAlign::Centre, VerticalAlign::Middle) }
 }
 impl Font {
     /// Mainly used for resizing when the window resolution is changed. If you want multiple
@@ -101,7 +277,10 @@ impl Font {
         mesh!(display, &[1, 0, 2, 2, 3, 1], &[
             vec3(0.0, 1.0, 0.0).into(), vec3(0.0, 0.0, 0.0).into(),
             vec3(1.0, 1.0, 0.0).into(), vec3(1.0, 0.0, 0.0).into()
-        ], &meshes::screen::UVS)
+        ], &[
+            TextureCoords::new(0.0, 1.0), TextureCoords::new(0.0, 0.0),
+            TextureCoords::new(1.0, 1.0), TextureCoords::new(1.0, 0.0)
+        ])
     }
     /// Checks if the font has a glyph for the provided char
     pub fn has_glyph(&self, c: char) -> bool {
@@ -109,7 +288,8 @@ impl Font {
     }
     /// Loads the shader for use in the `TextRenderer` struct.
     /// The fragment shader has these uniforms, `albedo` is the colour of the text, `tex` is
-    /// the texture of a glyph. also see the base vertex shader.
+    /// the atlas page of a glyph. The vertex shader also has `uv_rect`, the bottom left and top
+    /// right uvs of the glyph in its page.
     pub fn shader(display: &impl Facade) -> Result<Program, glium::ProgramCreationError> {
         Program::from_source(display, 
         "#version 140
@@ -120,9 +300,10 @@ impl Font {
         uniform mat4 model;
         uniform mat4 camera;
         uniform mat4 perspective;
+        uniform vec4 uv_rect;
 
         void main() {
-            uv = texture_coords;
+            uv = mix(uv_rect.xy, uv_rect.zw, texture_coords);
             gl_Position = perspective * camera * model * vec4(position, 1);
         }",
         "#version 140
@@ -148,12 +329,46 @@ impl Font {
         data: impl Deref<Target = [u8]>
     ) -> Result<Self, &'static str> {
         let scale = settings.scale;
+        #[cfg(feature = "shaping")]
+        let face = Some((data.to_vec(), settings.collection_index))
+            .filter(|(data, index)| rustybuzz::Face::from_slice(data, *index).is_some());
         let font = fontdue::Font::from_bytes(data, settings)?;
         Ok(Self {
             scale, font,
-            textures: HashMap::new()
+            atlas: GlyphAtlas::default(),
+            kerning: true,
+            #[cfg(feature = "shaping")]
+            face,
+            #[cfg(feature = "shaping")]
+            shaping: true
         })
     }
+    /// Sets whether the space between pairs of characters is adjusted using the font's kerning
+    /// table. on by default. fonts that only store kerning in their GPOS table are only kerned
+    /// with the `shaping` feature.
+    pub fn set_kerning(&mut self, kerning: bool) { self.kerning = kerning }
+    pub fn kerning(&self) -> bool { self.kerning }
+    /// Sets whether horizontal text is shaped, applying ligatures, combining marks and the
+    /// rules of complex scripts such as Arabic and Devanagari. on by default. each line is shaped
+    /// in the direction of its first letters so lines shouldn't mix left to right and right to
+    /// left text.
+    #[cfg(feature = "shaping")]
+    pub fn set_shaping(&mut self, shaping: bool) { self.shaping = shaping }
+    #[cfg(feature = "shaping")]
+    pub fn shaping(&self) -> bool { self.shaping }
+    /// Replaces the glyph atlas, e.g. with one with bigger pages for large text.
+    pub fn with_atlas(mut self, atlas: GlyphAtlas) -> Self {
+        self.atlas = atlas;
+        self
+    }
+    /// The atlas that loaded glyphs are packed into.
+    pub fn atlas(&self) -> &GlyphAtlas { &self.atlas }
+    /// Gets the index of a character's glyph in the font, or the replacement char's glyph if
+    /// the font doesn't have it.
+    pub fn glyph_index(&self, c: char) -> u16 {
+        let c = if self.font.has_glyph(c) { c } else { char::REPLACEMENT_CHARACTER };
+        self.font.lookup_glyph_index(c)
+    }
     /// Gets character data for rendering **without** loading it to memmory. It is recommended
     /// to use `load_and_get` instead.
     pub fn char_data(&self, c: char, display: &impl Facade) -> (Metrics, Option<Texture2d>) {
@@ -168,12 +383,18 @@ impl Font {
         };
         (metrics, Some(Texture2d::new(display, data).unwrap()))
     }
-    /// Loads a characters texture and offset data to memmory for drawing unless the font file
-    /// doesnt contatin said character.
+    /// Gets a glyph's offset data and place in the atlas, rasterizing it into the atlas if it
+    /// isn't currently loaded.
+    pub fn load_glyph(&mut self, glyph: u16, display: &impl Facade) -> Result<(Metrics, Option<AtlasGlyph>), TextureCreationError> {
+        if let Some(loaded) = self.atlas.get(glyph) { return Ok(loaded) }
+        let (metrics, data) = self.font.rasterize_indexed(glyph, self.scale);
+        let region = self.atlas.insert(display, glyph, metrics, &data)?;
+        Ok((metrics, region))
+    }
+    /// Loads a characters texture and offset data to the atlas for drawing. panics if the atlas
+    /// texture couldn't be created.
     pub fn load_char(&mut self, c: char, display: &impl Facade) {
-        if self.font.has_glyph(c) {
-            self.textures.insert(c, self.char_data(c, display));
-        }
+        self.load_glyph(self.glyph_index(c), display).expect("failed to create glyph atlas texture");
     }
     /// Loads all characters that are valid in the font file to memory. (not recommended)
     pub fn load_all(&mut self, display: &impl Facade) {
@@ -181,25 +402,15 @@ impl Font {
             self.load_char(c, display);
         }
     }
-    /// Tries to get texture and offset data. If the character is not in the font file
-    /// returns replacement char data instead
-    pub fn try_get(&self, mut c: char) -> Option<(Metrics, Option<&Texture2d>)> {
-        if !self.font.has_glyph(c) { c = char::REPLACEMENT_CHARACTER }
-        self.textures.get(&c).map(|(a, b)| (*a, b.as_ref()))
-    }
-    /// Gets texture and offset data of a chararcter from memory, loading it if it isn't currently
-    /// loaded.
-    pub fn load_and_get(&mut self, c: char, display: &impl Facade) -> (Metrics, Option<&Texture2d>) {
-        let load = self.try_get(c).is_none();
-        if load { self.load_char(c, display) }
-        self.try_get(c).unwrap_or((Metrics {
-            xmin: 0, ymin: 0, width: 0, height: 0,
-            advance_width: 0.0, advance_height: 0.0,
-            bounds: OutlineBounds { 
-                xmin: 0.0, ymin: 0.0,
-                width: 0.0, height: 0.0
-            }
-        }, None))
+    /// Tries to get offset data and the place in the atlas of a loaded character. If the
+    /// character is not in the font file returns replacement char data instead
+    pub fn try_get(&self, c: char) -> Option<(Metrics, Option<AtlasGlyph>)> {
+        self.atlas.peek(self.glyph_index(c))
+    }
+    /// Gets offset data and the place in the atlas of a chararcter, loading it if it isn't
+    /// currently loaded. panics if the atlas texture couldn't be created.
+    pub fn load_and_get(&mut self, c: char, display: &impl Facade) -> (Metrics, Option<AtlasGlyph>) {
+        self.load_glyph(self.glyph_index(c), display).expect("failed to create glyph atlas texture")
     }
     /// Formats text so that tabs are replaced with `tab_indent` spaces and text is wrapped every
     /// time a words width exceeds `wrap`.
@@ -209,53 +420,230 @@ impl Font {
         if let Some(wrap) = wrap { self.wrap_text(&text, wrap, display) }
         else { text }
     }
-    /// Wraps text so that each word that excedes `wrap` in width is put on a new line.
+    /// Wraps text so that each word that excedes `wrap` in width is put on a new line. widths
+    /// are measured the same way `layout()` measures them, so shaped text wraps where it is
+    /// drawn. the glyphs are loaded to the atlas ready for drawing, panics if the atlas texture
+    /// couldn't be created.
     pub fn wrap_text(&mut self, text: &str, wrap: f32, display: &impl Facade) -> String {
+        let shaped = self.shape_lines(text);
+        for glyph in shaped.iter().flatten() {
+            self.load_glyph(glyph.glyph, display).expect("failed to create glyph atlas texture");
+        }
+        self.wrap(text, shaped, wrap)
+    }
+    fn wrap(&self, text: &str, shaped: Vec<Vec<ShapedGlyph>>, wrap: f32) -> String {
         let wrap = wrap * self.scale;
         let mut lines = String::new();
-        let mut width = 0.0;
-        let mut word  = String::new();
-        let mut word_width = 0.0;
-        let mut space = String::new();
-        
-        for c in text.chars() {
-            let (metrics, _) = self.load_and_get(c, display);
-            let advance = if metrics.advance_width == 0.0 { metrics.advance_height }
-                else { metrics.advance_width };
 
-            let new_width = width + word_width + advance;
-            let whitespace = c.is_whitespace();
+        for (i, (line, shaped)) in text.lines().zip(shaped).enumerate() {
+            if i > 0 { lines.push('\n') }
+            // the width of each character, a ligature's width is given to its first character
+            let mut advances = HashMap::new();
+            for glyph in shaped { *advances.entry(glyph.cluster).or_insert(0.0) += glyph.advance }
+
+            let mut width = 0.0;
+            let mut word  = String::new();
+            let mut word_width = 0.0;
+            let mut space = String::new();
+            for (index, c) in line.char_indices() {
+                let advance = match advances.get(&index) {
+                    Some(advance) if *advance != 0.0 => *advance,
+                    // vertical fonts only have an advance height
+                    _ => self.font.metrics(c, self.scale).advance_height
+                };
+                let new_width = width + word_width + advance;
+                let whitespace = c.is_whitespace();
 
-            if c == '\n' {
-                lines.push_str(&word);
-                lines.push('\n');
-                space.clear();
-                word.clear();
-                word_width = 0.0;
-                width = 0.0;
-            } else if new_width >= wrap && !whitespace {
-                lines.push('\n');
-                word.push(c);
-                space.clear();
-                width = 0.0;
-            } else if whitespace {
-                lines.push_str(&word);
-                width = new_width;
-                word_width = 0.0;
-                word.clear();
-                space.push(c);
-            } else {
-                lines.push_str(&space);
-                space.clear();
-                word.push(c);
-                word_width += metrics.advance_width;
+                if new_width >= wrap && !whitespace {
+                    lines.push('\n');
+                    word.push(c);
+                    space.clear();
+                    width = 0.0;
+                } else if whitespace {
+                    lines.push_str(&word);
+                    width = new_width;
+                    word_width = 0.0;
+                    word.clear();
+                    space.push(c);
+                } else {
+                    lines.push_str(&space);
+                    space.clear();
+                    word.push(c);
+                    word_width += advance;
+                }
             }
+            lines.push_str(&word);
         }
-        lines.push_str(&word);
+        if text.ends_with('\n') { lines.push('\n') }
         lines
     }
+    /// Measures horizontal text at the current scale without loading any glyphs, giving the
+    /// same positions `TextRenderer::draw` draws it at. carets are placed for left to right
+    /// text.
+    /// ```no_run
+    /// # use thin_engine::{text_renderer::*, prelude::*};
+    /// # fn f(font: &Font) {
+    /// // centre a label on the origin
+    /// let layout = font.layout("Start Game");
+    /// let model = Mat4::from_pos(vec3(-layout.size.x / 2.0, layout.size.y / 2.0, 0.0));
+    /// # }
+    /// ```
+    pub fn layout(&self, text: &str) -> TextLayout {
+        let size = 1.0 / self.scale;
+        // fonts without line metrics are spaced using their scale
+        let line_metric = self.horizontal_metrics().or(self.vertical_metrics()).unwrap_or(LineMetrics {
+            ascent: self.scale, descent: 0.0, line_gap: 0.0, new_line_size: self.scale
+        });
+        let mut layout = TextLayout {
+            size: Vec2::ZERO, ink_min: Vec2::ZERO, ink_max: Vec2::ZERO,
+            lines: Vec::new(), glyphs: Vec::new(), carets: Vec::new()
+        };
+        let mut baseline = -line_metric.ascent * size;
+        for (i, (line, shaped)) in text.lines().zip(self.shape_lines(text)).enumerate() {
+            let start = line.as_ptr() as usize - text.as_ptr() as usize;
+            let first = layout.glyphs.len();
+            let mut carets = HashMap::new();
+            let mut x = 0.0;
+            for shaped in shaped {
+                let metrics = self.font.metrics_indexed(shaped.glyph, self.scale);
+                let bounds = metrics.bounds;
+                let position = vec2(x, baseline);
+                carets.entry(shaped.cluster).or_insert(x);
+                layout.glyphs.push(GlyphLayout {
+                    glyph: shaped.glyph, index: start + shaped.cluster, line: i, position,
+                    min: position + (vec2(bounds.xmin, bounds.ymin) + shaped.offset).scale(size),
+                    size: vec2(bounds.width, bounds.height).scale(size),
+                    advance: shaped.advance * size
+                });
+                x += shaped.advance * size;
+            }
+            // characters inside a ligature share its caret
+            let mut caret = 0.0;
+            for index in line.char_indices().map(|(i, _)| i).chain([line.len()]) {
+                caret = if index == line.len() { x } else { carets.get(&index).copied().unwrap_or(caret) };
+                layout.carets.push(Caret { index: start + index, line: i, position: vec2(caret, baseline) });
+            }
+            layout.lines.push(LineLayout {
+                text: start..start + line.len(), glyphs: first..layout.glyphs.len(), width: x, baseline
+            });
+            layout.size.x = layout.size.x.max(x);
+            baseline -= line_metric.new_line_size * size;
+        }
+        if let Some(last) = layout.lines.last() {
+            layout.size.y = -(last.baseline + line_metric.descent * size);
+        }
+        layout.measure_ink();
+        layout
+    }
+    /// Measures text and aligns it in a box. see `TextLayout::align()`.
+    pub fn layout_aligned(&self, text: &str, bounds: Vec2, align: TextAlign) -> TextLayout {
+        let mut layout = self.layout(text);
+        layout.align(text, bounds, align);
+        layout
+    }
+    /// Lays out `text` the same way `TextRenderer` draws it, loading its glyphs and running
+    /// `place` with the bottom left and size of each visible glyph. the top of the first line
+    /// is at 0 unless it is aligned and the font's scale is 1 unit. vertical text isn't
+    /// aligned.
+    fn place_glyphs(
+        &mut self, text: &str, display: &impl Facade, align: Option<(Vec2, TextAlign)>,
+        mut place: impl FnMut(Vec2, Vec2, AtlasGlyph, &GlyphAtlas) -> Result<(), TextDrawError>
+    ) -> Result<(), TextDrawError> {
+        let size = 1.0 / self.scale;
+
+        let v_line = self.vertical_metrics();
+        let h_line = self.horizontal_metrics();
+
+        let (vertical, line_metric) = match (v_line, h_line) {
+            (Some(v), Some(h)) => {
+                let Some(c) = text.chars().next() else { return Ok(()) };
+                let (metrics, _) = self.load_glyph(self.glyph_index(c), display)?;
+                let vertical = metrics.advance_height == 0.0;
+                (vertical, if vertical { v } else { h })
+            },
+            (Some(v), None) => (true,  v),
+            (None, Some(h)) => (false, h),
+            (None, None) => Err(TextDrawError::NoNewLineData)?
+        };
+
+        if vertical {
+            let mut pos = vec2(-line_metric.ascent * size, 0.0);
+            for line in text.lines() {
+                for c in line.chars() {
+                    let (metrics, glyph) = self.load_glyph(self.glyph_index(c), display)?;
+                    let bounds = metrics.bounds;
+                    let offset = vec2(bounds.xmin * size, bounds.ymin * size);
+                    if let Some(glyph) = glyph {
+                        place(pos + offset, vec2(bounds.width*size, bounds.height*size), glyph, &self.atlas)?
+                    }
+                    pos.y += metrics.advance_height * size;
+                }
+                pos.y = 0.0; pos.x -= line_metric.new_line_size * size;
+            }
+            return Ok(())
+        }
+        let layout = match align {
+            Some((bounds, align)) => self.layout_aligned(text, bounds, align),
+            None => self.layout(text)
+        };
+        for layout in layout.glyphs {
+            if let (_, Some(glyph)) = self.load_glyph(layout.glyph, display)? {
+                place(layout.min, layout.size, glyph, &self.atlas)?
+            }
+        }
+        Ok(())
+    }
+    /// turns each line of `text` into glyphs and their horizontal spacing.
+    fn shape_lines(&self, text: &str) -> Vec<Vec<ShapedGlyph>> {
+        #[cfg(feature = "shaping")]
+        if self.shaping {
+            let face = self.face.as_ref().and_then(|(data, index)| rustybuzz::Face::from_slice(data, *index));
+            if let Some(face) = face {
+                return text.lines().map(|line| self.shape(&face, line)).collect()
+            }
+        }
+        text.lines().map(|line| {
+            let mut glyphs: Vec<ShapedGlyph> = Vec::new();
+            for (cluster, c) in line.char_indices() {
+                let glyph = self.glyph_index(c);
+                let kern = glyphs.last().filter(|_| self.kerning)
+                    .and_then(|i| self.font.horizontal_kern_indexed(i.glyph, glyph, self.scale));
+                if let (Some(kern), Some(last)) = (kern, glyphs.last_mut()) { last.advance += kern }
+                let advance = self.font.metrics_indexed(glyph, self.scale).advance_width;
+                glyphs.push(ShapedGlyph { glyph, cluster, offset: Vec2::ZERO, advance });
+            }
+            glyphs
+        }).collect()
+    }
+    /// shapes a line with rustybuzz, which also applies kerning unless it is turned off.
+    #[cfg(feature = "shaping")]
+    fn shape(&self, face: &rustybuzz::Face, line: &str) -> Vec<ShapedGlyph> {
+        let mut buffer = rustybuzz::UnicodeBuffer::new();
+        buffer.push_str(line);
+        buffer.guess_segment_properties();
+        let features = if self.kerning { Vec::new() } else {
+            vec![rustybuzz::Feature::new(rustybuzz::ttf_parser::Tag::from_bytes(b"kern"), 0, ..)]
+        };
+        let output = rustybuzz::shape(face, &features, buffer);
+        let scale = self.scale / face.units_per_em() as f32;
+        output.glyph_infos().iter().zip(output.glyph_positions()).map(|(info, pos)| {
+            // missing characters are drawn as the replacement char, the same as without shaping
+            let (glyph, advance) = match info.glyph_id {
+                0 => {
+                    let glyph = self.glyph_index(char::REPLACEMENT_CHARACTER);
+                    (glyph, self.font.metrics_indexed(glyph, self.scale).advance_width)
+                },
+                glyph => (glyph as u16, pos.x_advance as f32 * scale)
+            };
+            ShapedGlyph {
+                glyph, advance,
+                cluster: info.cluster as usize,
+                offset: vec2(pos.x_offset as f32, pos.y_offset as f32).scale(scale)
+            }
+        }).collect()
+    }
     /// Clears all loaded textures and offset data.
-    pub fn clear_loaded(&mut self) { self.textures.clear() }
+    pub fn clear_loaded(&mut self) { self.atlas.clear() }
     /// metrics on line spacing for horizontal lines
     pub fn horizontal_metrics(&self) -> Option<LineMetrics> {
         self.font.horizontal_line_metrics(self.scale)
@@ -276,13 +664,54 @@ pub struct TextRenderer<'a, F: Facade> {
 #[derive(Debug)]
 pub enum TextDrawError {
     GliumDrawError(glium::DrawError),
-    NoNewLineData
+    NoNewLineData,
+    /// a glyph atlas page couldn't be created.
+    TextureCreationError(TextureCreationError),
+    /// the buffers of a `TextBatch` couldn't be created.
+    MeshError(MeshError),
+    /// an atlas page used by a `TextBatch` was cleared after its text was added, e.g. to make
+    /// room for other glyphs. the batch has to be cleared and its text added again.
+    StaleAtlasPage(usize)
+}
+impl std::fmt::Display for TextDrawError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::GliumDrawError(e) => e.fmt(f),
+            Self::NoNewLineData => write!(f, "the font has no line metrics"),
+            Self::TextureCreationError(e) => write!(f, "failed to create glyph atlas page: {e}"),
+            Self::MeshError(MeshError::VertexErr(e)) => write!(f, "failed to create text batch buffers: {e}"),
+            Self::MeshError(MeshError::IndexErr(e))  => write!(f, "failed to create text batch buffers: {e}"),
+            Self::StaleAtlasPage(page) => write!(f, "atlas page {page} was cleared after text was added to the batch")
+        }
+    }
+}
+impl std::error::Error for TextDrawError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::GliumDrawError(e) => Some(e),
+            Self::NoNewLineData => None,
+            Self::TextureCreationError(e) => Some(e),
+            Self::MeshError(MeshError::VertexErr(e)) => Some(e),
+            Self::MeshError(MeshError::IndexErr(e))  => Some(e),
+            Self::StaleAtlasPage(_) => None
+        }
+    }
 }
 impl From<glium::DrawError> for TextDrawError {
     fn from(err: glium::DrawError) -> TextDrawError {
         Self::GliumDrawError(err)
     }
 }
+impl From<MeshError> for TextDrawError {
+    fn from(err: MeshError) -> TextDrawError {
+        Self::MeshError(err)
+    }
+}
+impl From<TextureCreationError> for TextDrawError {
+    fn from(err: TextureCreationError) -> TextDrawError {
+        Self::TextureCreationError(err)
+    }
+}
 #[derive(Debug)]
 pub enum DrawValidError {
     InvalidChar(char),
@@ -316,51 +745,331 @@ impl<'a, F: Facade> TextRenderer<'a, F> {
         model: Mat4, perspective: Mat4,
         camera: Mat4, font: &mut Font
     ) -> Result<(), TextDrawError> {
-        let size = 1.0 / font.scale;
+        self.draw_with(text, colour, frame, model, perspective, camera, font, None)
+    }
+    /// Draws text aligned in a box with its top left at the origin of `model` that is `bounds`
+    /// wide and tall, in units of the font's scale. see `TextLayout::align()`.
+    /// ```no_run
+    /// # use thin_engine::{text_renderer::*, prelude::*};
+    /// # fn f(text_renderer: &TextRenderer<Display>, frame: &mut Frame, font: &mut Font) -> Result<(), ThinError> {
+    /// # let (perspective, camera) = (Mat4::default(), Mat4::default());
+    /// // a button 8 by 2 characters tall with its label in the middle
+    /// text_renderer.draw_aligned(
+    ///     "Start Game", Vec3::ONE, frame,
+    ///     Mat4::from_pos_and_scale(vec3(-0.4, 0.1, 0.0), Vec3::splat(0.1)),
+    ///     perspective, camera, font, vec2(8.0, 2.0), TextAlign::centred()
+    /// )?;
+    /// # Ok(()) }
+    /// ```
+    pub fn draw_aligned(
+        &self, text: &str, colour: Vec3,
+        frame: &mut impl Surface,
+        model: Mat4, perspective: Mat4,
+        camera: Mat4, font: &mut Font,
+        bounds: Vec2, align: TextAlign
+    ) -> Result<(), TextDrawError> {
+        self.draw_with(text, colour, frame, model, perspective, camera, font, Some((bounds, align)))
+    }
+    fn draw_with(
+        &self, text: &str, colour: Vec3,
+        frame: &mut impl Surface,
+        model: Mat4, perspective: Mat4,
+        camera: Mat4, font: &mut Font,
+        align: Option<(Vec2, TextAlign)>
+    ) -> Result<(), TextDrawError> {
+        font.place_glyphs(text, self.display, align, |pos, size, glyph, atlas| {
+            let draw_mat = Mat4::from_pos_and_scale(pos.extend(0.0), size.extend(1.0));
+            Ok(frame.draw(
+                (self.vertices, self.uvs), self.indices,
+                self.shader, &uniform! {
+                    camera: camera, perspective: perspective,
+                    model: model * draw_mat, albedo: colour,
+                    uv_rect: [glyph.uv_min.x, glyph.uv_min.y, glyph.uv_max.x, glyph.uv_max.y],
+                    tex: atlas.page(glyph.page).expect("glyph is in the atlas")
+                        .sampled().wrap_function(SamplerWrapFunction::Clamp)
+                }, self.draw_params
+            )?)
+        })
+    }
+}
+/// a corner of a glyph in a `TextBatch`.
+#[derive(Debug, Clone, Copy)]
+pub struct GlyphVertex {
+    pub position: (f32, f32, f32),
+    pub texture_coords: (f32, f32),
+    pub colour: (f32, f32, f32)
+}
+glium::implement_vertex!(GlyphVertex, position, texture_coords, colour);
+/// Draws many strings with one draw call for each atlas page they use, which is usually just
+/// one. the buffers are kept and only grow, so clearing and adding text every frame doesn't
+/// allocate once they are big enough. a batch should only be used with one font.
+/// ```no_run
+/// use thin_engine::{text_renderer::*, prelude::*};
+/// # fn f(display: &Display, frame: &mut Frame, font: &mut Font, draw_params: &DrawParameters) -> Result<(), ThinError> {
+/// let shader = TextBatch::shader(display)?;
+/// let mut batch = TextBatch::new();
+/// # let (perspective, camera) = (Mat4::default(), Mat4::default());
+/// // every frame
+/// batch.clear();
+/// batch.add("score: 100", vec3(1.0, 1.0, 1.0), Mat4::from_scale(Vec3::splat(0.1)), font, display)?;
+/// batch.add("lives: 3", vec3(1.0, 0.2, 0.2), Mat4::from_pos(vec3(0.0, -0.1, 0.0)), font, display)?;
+/// batch.draw(frame, display, &shader, font, perspective, camera, draw_params)?;
+/// # Ok(()) }
+/// ```
+#[derive(Default)]
+pub struct TextBatch {
+    /// the corners of each glyph, grouped by atlas page.
+    pages: Vec<Vec<GlyphVertex>>,
+    /// the generation of each atlas page when its first glyph was added.
+    generations: Vec<u64>,
+    vertices: Option<VertexBuffer<GlyphVertex>>,
+    indices: Option<IndexBuffer<u32>>,
+    /// true if glyphs were added or removed since the buffers were last written.
+    changed: bool
+}
+impl TextBatch {
+    pub fn new() -> Self { Self::default() }
+    /// Loads the shader for drawing a `TextBatch`. it has `perspective` and `camera` uniforms
+    /// like the base vertex shader and `tex`, the atlas page being drawn.
+    pub fn shader(display: &impl Facade) -> Result<Program, glium::ProgramCreationError> {
+        Program::from_source(display,
+        "#version 140
+        in vec3 position;
+        in vec2 texture_coords;
+        in vec3 colour;
 
-        let v_line = font.vertical_metrics();
-        let h_line = font.horizontal_metrics();
+        out vec2 uv;
+        out vec3 albedo;
+        uniform mat4 camera;
+        uniform mat4 perspective;
 
-        let (vertical, line_metric) = match (v_line, h_line) {
-            (Some(v), Some(h)) => {
-                let Some(c) = text.chars().next() else { return Ok(()) };
-                let (metrics, _) = font.load_and_get(c, self.display);
-                let vertical = metrics.advance_height == 0.0;
-                (vertical, if vertical { v } else { h })
-            },
-            (Some(v), None) => (true,  v),
-            (None, Some(h)) => (false, h),
-            (None, None) => Err(TextDrawError::NoNewLineData)?
+        void main() {
+            uv = texture_coords;
+            albedo = colour;
+            gl_Position = perspective * camera * vec4(position, 1);
+        }",
+        "#version 140
+        in vec2 uv;
+        in vec3 albedo;
+        out vec4 colour;
+        uniform sampler2D tex;
+        void main() {
+            colour = texture(tex, uv).rrrr * vec4(albedo, 1);
+        }", None)
+    }
+    /// Adds text laid out the same as `TextRenderer::draw`, transformed by `model`.
+    pub fn add(
+        &mut self, text: &str, colour: Vec3,
+        model: Mat4, font: &mut Font, display: &impl Facade
+    ) -> Result<(), TextDrawError> {
+        self.add_with(text, colour, model, font, display, None)
+    }
+    /// Adds text laid out the same as `TextRenderer::draw_aligned`, transformed by `model`.
+    pub fn add_aligned(
+        &mut self, text: &str, colour: Vec3,
+        model: Mat4, font: &mut Font, display: &impl Facade,
+        bounds: Vec2, align: TextAlign
+    ) -> Result<(), TextDrawError> {
+        self.add_with(text, colour, model, font, display, Some((bounds, align)))
+    }
+    fn add_with(
+        &mut self, text: &str, colour: Vec3,
+        model: Mat4, font: &mut Font, display: &impl Facade,
+        align: Option<(Vec2, TextAlign)>
+    ) -> Result<(), TextDrawError> {
+        let (pages, generations) = (&mut self.pages, &mut self.generations);
+        font.place_glyphs(text, display, align, |pos, size, glyph, atlas| {
+            if pages.len() <= glyph.page {
+                pages.resize_with(glyph.page + 1, Vec::new);
+                generations.resize(glyph.page + 1, 0);
+            }
+            // a page cleared while the batch has glyphs in it keeps its old generation so
+            // `draw` can tell
+            if pages[glyph.page].is_empty() {
+                generations[glyph.page] = atlas.generation(glyph.page).expect("glyph is in the atlas");
+            }
+            let (min, max) = (glyph.uv_min, glyph.uv_max);
+            let corner = |x: f32, y: f32, u: f32, v: f32| {
+                let position = (model * vec4(pos.x + size.x * x, pos.y + size.y * y, 0.0, 1.0)).truncate();
+                GlyphVertex {
+                    position: (position.x, position.y, position.z),
+                    texture_coords: (u, v),
+                    colour: (colour.x, colour.y, colour.z)
+                }
+            };
+            pages[glyph.page].extend([
+                corner(0.0, 1.0, min.x, max.y), corner(0.0, 0.0, min.x, min.y),
+                corner(1.0, 1.0, max.x, max.y), corner(1.0, 0.0, max.x, min.y)
+            ]);
+            Ok(())
+        })?;
+        self.changed = true;
+        Ok(())
+    }
+    /// Removes all text while keeping the buffers for reuse.
+    pub fn clear(&mut self) {
+        for page in &mut self.pages { page.clear() }
+        self.changed = true;
+    }
+    /// the amount of glyphs in the batch.
+    pub fn len(&self) -> usize { self.pages.iter().map(|i| i.len() / 4).sum() }
+    /// returns true if there are no glyphs in the batch.
+    pub fn is_empty(&self) -> bool { self.pages.iter().all(|i| i.is_empty()) }
+    /// writes the glyphs to the buffers, growing them if they are too small.
+    fn upload(&mut self, display: &impl Facade) -> Result<(), MeshError> {
+        let glyphs = self.len();
+        if self.vertices.as_ref().is_none_or(|i| i.len() < glyphs * 4) {
+            let capacity = glyphs.next_power_of_two() as u32;
+            let indices = (0..capacity).flat_map(|i| [1, 0, 2, 2, 3, 1].map(|j| i * 4 + j)).collect::<Vec<_>>();
+            self.indices = Some(IndexBuffer::new(display, PrimitiveType::TrianglesList, &indices)?);
+            self.vertices = Some(VertexBuffer::empty_dynamic(display, capacity as usize * 4)?);
+        }
+        let vertices = self.vertices.as_ref().expect("buffers were just created");
+        let mut start = 0;
+        for page in self.pages.iter().filter(|i| !i.is_empty()) {
+            if let Some(slice) = vertices.slice(start..start + page.len()) { slice.write(page) }
+            start += page.len();
+        }
+        self.changed = false;
+        Ok(())
+    }
+    /// Draws every glyph in the batch. returns `TextDrawError::StaleAtlasPage` without drawing
+    /// if an atlas page the batch uses was cleared since its text was added, which happens when
+    /// the font is resized, its atlas is cleared or more glyphs are loaded than fit in the atlas.
+    pub fn draw(
+        &mut self, frame: &mut impl Surface, display: &impl Facade,
+        shader: &Program, font: &Font, perspective: Mat4,
+        camera: Mat4, draw_params: &DrawParameters
+    ) -> Result<(), TextDrawError> {
+        if self.is_empty() { return Ok(()) }
+        for (page, (glyphs, generation)) in self.pages.iter().zip(&self.generations).enumerate() {
+            if !glyphs.is_empty() && font.atlas.generation(page) != Some(*generation) {
+                return Err(TextDrawError::StaleAtlasPage(page))
+            }
+        }
+        if self.changed { self.upload(display)? }
+        let (Some(vertices), Some(indices)) = (&self.vertices, &self.indices) else { return Ok(()) };
+        let mut start = 0;
+        for (page, glyphs) in self.pages.iter().enumerate() {
+            let end = start + glyphs.len() / 4 * 6;
+            if let (Some(texture), Some(indices)) = (font.atlas.page(page), indices.slice(start..end)) {
+                if start != end { frame.draw(vertices, indices, shader, &uniform! {
+                    camera: camera, perspective: perspective,
+                    tex: texture.sampled().wrap_function(SamplerWrapFunction::Clamp)
+                }, draw_params)? }
+            }
+            start = end;
+        }
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn font() -> Font { Font::from_scale_and_file(20.0, "examples/DroidSans.ttf").unwrap() }
+    #[test]
+    fn wrapped_lines_fit() {
+        let font = font();
+        let text = "the quick brown fox jumps over the lazy dog\nshort line";
+        let wrapped = font.wrap(text, font.shape_lines(text), 6.0);
+        let layout = font.layout(&wrapped);
+        assert!(layout.lines.len() > 3, "{wrapped:?}");
+        for line in &layout.lines { assert!(line.width < 6.0, "{wrapped:?}") }
+        assert_eq!(wrapped.split_whitespace().collect::<Vec<_>>(), text.split_whitespace().collect::<Vec<_>>());
+        assert_eq!(font.wrap("a b\n", font.shape_lines("a b\n"), 100.0), "a b\n");
+    }
+    #[test]
+    fn layout_lines_and_carets() {
+        let layout = font().layout("ab\ncd");
+        assert_eq!(layout.lines.iter().map(|i| i.text.clone()).collect::<Vec<_>>(), [0..2, 3..5]);
+        assert!(layout.lines[1].baseline < layout.lines[0].baseline);
+        assert_eq!(layout.size.x, layout.lines[0].width.max(layout.lines[1].width));
+        assert_eq!(layout.carets.iter().map(|i| i.index).collect::<Vec<_>>(), [0, 1, 2, 3, 4, 5]);
+        assert_eq!(layout.caret(1).unwrap().position.x, layout.glyphs[0].advance);
+        let end = vec2(layout.lines[1].width + 1.0, layout.lines[1].baseline);
+        assert_eq!(layout.closest_caret(end).unwrap().index, 5);
+    }
+    /// lays out text where every character is 1 unit square and lines are 1 unit apart.
+    fn monospace(text: &str) -> TextLayout {
+        let mut layout = TextLayout {
+            size: Vec2::ZERO, ink_min: Vec2::ZERO, ink_max: Vec2::ZERO,
+            lines: Vec::new(), glyphs: Vec::new(), carets: Vec::new()
         };
+        for (i, line) in text.lines().enumerate() {
+            let start = line.as_ptr() as usize - text.as_ptr() as usize;
+            let baseline = -(i as f32 + 1.0);
+            let first = layout.glyphs.len();
+            for (x, (index, _)) in line.char_indices().enumerate() {
+                let position = vec2(x as f32, baseline);
+                layout.glyphs.push(GlyphLayout {
+                    glyph: 0, index: start + index, line: i, position, min: position, size: Vec2::ONE, advance: 1.0
+                });
+                layout.carets.push(Caret { index: start + index, line: i, position });
+            }
+            let width = line.chars().count() as f32;
+            layout.carets.push(Caret { index: start + line.len(), line: i, position: vec2(width, baseline) });
+            layout.lines.push(LineLayout { text: start..start + line.len(), glyphs: first..layout.glyphs.len(), width, baseline });
+            layout.size = vec2(layout.size.x.max(width), i as f32 + 1.0);
+        }
+        layout.measure_ink();
+        layout
+    }
+    fn aligned(text: &str, bounds: Vec2, horizontal: HorizontalAlign, vertical: VerticalAlign) -> TextLayout {
+        let mut layout = monospace(text);
+        layout.align(text, bounds, TextAlign::new(horizontal, vertical));
+        layout
+    }
+    #[test]
+    fn align_in_box() {
+        use {HorizontalAlign::*, VerticalAlign::*};
+        let text = "ab\nabcd";
+        let bounds = vec2(10.0, 4.0);
+        let left = aligned(text, bounds, Left, Top);
+        assert_eq!(left.glyphs, monospace(text).glyphs);
 
-        let mut pos = if vertical { vec2(-line_metric.ascent * size, 0.0) }
-                             else { vec2(0.0, -line_metric.ascent * size) };
+        let centred = aligned(text, bounds, Centre, Middle);
+        assert_eq!(centred.glyphs[0].position, vec2(4.0, -2.0));
+        assert_eq!(centred.glyphs[2].position, vec2(3.0, -3.0));
+        assert_eq!((centred.ink_min, centred.ink_max), (vec2(3.0, -3.0), vec2(7.0, -1.0)));
+        assert_eq!(centred.size, vec2(4.0, 2.0));
 
-        for line in text.lines() {
-            for c in line.chars() {
-                let (metrics, tex) = font.load_and_get(c, self.display);
-                let bounds = metrics.bounds;
-                let offset = vec2(bounds.xmin * size, bounds.ymin * size);
-                let draw_mat = Mat4::from_pos_and_scale(
-                    (pos + offset).extend(0.0),
-                    vec3(bounds.width*size, bounds.height*size, 1.0)
-                );
+        let right = aligned(text, bounds, Right, Bottom);
+        assert_eq!(right.lines.iter().map(|i| i.baseline).collect::<Vec<_>>(), [-3.0, -4.0]);
+        assert_eq!(right.glyphs[1].position.x, 9.0);
+        assert_eq!(right.carets[2].position, vec2(10.0, -3.0));
 
-                if let Some(tex) = tex { frame.draw(
-                    (self.vertices, self.uvs), self.indices,
-                    self.shader, &uniform! {
-                        camera: camera, perspective: perspective,
-                        model: model * draw_mat, albedo: colour,
-                        tex: tex.sampled().wrap_function(SamplerWrapFunction::Clamp)
-                    }, self.draw_params
-                )? }
+        assert_eq!(aligned(text, bounds, Left, Baseline).lines[0].baseline, 0.0);
+        // with no size the text is aligned around the origin
+        assert_eq!(aligned("abcd", Vec2::ZERO, Centre, Middle).ink_min, vec2(-2.0, -0.5));
+    }
+    #[test]
+    fn justify_widens_spaces() {
+        let justified = |text| aligned(text, vec2(9.0, 0.0), HorizontalAlign::Justified, VerticalAlign::Top);
+        let x = |layout: &TextLayout, line| layout.glyphs.iter().filter(|i| i.line == line).map(|i| i.position.x).collect::<Vec<_>>();
+        let layout = justified("a b c\nab");
+        assert_eq!(x(&layout, 0), [0.0, 1.0, 4.0, 5.0, 8.0]);
+        assert_eq!(layout.lines[0].width, 9.0);
+        assert_eq!(layout.caret(5).unwrap().position.x, 9.0);
+        // the last line isn't stretched
+        assert_eq!(x(&layout, 1), [0.0, 1.0]);
+        assert_eq!(layout.lines[1].width, 2.0);
+        assert_eq!(layout.size.x, 9.0);
 
-                if vertical { pos.y += metrics.advance_height * size }
-                else        { pos.x += metrics.advance_width  * size }
-            }
-            if vertical { pos.y = 0.0; pos.x -= line_metric.new_line_size * size }
-            else        { pos.x = 0.0; pos.y -= line_metric.new_line_size * size }
-        }
-        Ok(())
+        // a trailing space doesn't stop the last word reaching the edge
+        let layout = justified("a b c \nab");
+        assert_eq!(x(&layout, 0), [0.0, 1.0, 4.0, 5.0, 8.0, 9.0]);
+        assert_eq!(layout.lines[0].width, 9.0);
+    }
+    #[cfg(feature = "shaping")]
+    #[test]
+    fn shaped_missing_chars_are_the_replacement_char() {
+        let mut font = font();
+        let text = "a\u{E000}b";
+        let glyphs = |font: &Font| font.layout(text).glyphs.iter().map(|i| (i.glyph, i.advance)).collect::<Vec<_>>();
+        let shaped = glyphs(&font);
+        font.set_shaping(false);
+        assert_eq!(shaped[1], glyphs(&font)[1]);
+        assert_eq!(shaped[1].0, font.glyph_index(char::REPLACEMENT_CHARACTER));
+        assert_ne!(shaped[1].0, 0);
     }
 }
@@ -0,0 +1,172 @@
+//! Packs rasterized glyphs into shared textures so text can be drawn without switching textures.
+//!
+//! Glyphs are packed into rows of pages, and a new page is added when a glyph doesn't fit.
+//! Once there are `max_pages` pages the least recently used one is cleared and reused.
+use crate::prelude::*;
+use std::{borrow::Cow, collections::HashMap, sync::atomic::{AtomicU64, Ordering}};
+use glium::{
+    backend::Facade, Rect,
+    texture::{RawImage2d, ClientFormat, MipmapsOption, UncompressedFloatFormat, TextureCreationError}
+};
+use fontdue::Metrics;
+
+/// empty pixels left around each glyph so they don't bleed into each other when sampled.
+const PADDING: u32 = 1;
+/// unique across every atlas so a page from another atlas never has the same generation.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+fn next_generation() -> u64 { GENERATION.fetch_add(1, Ordering::Relaxed) }
+
+/// where a glyph is in the atlas.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AtlasGlyph {
+    /// the index of the page texture the glyph is in.
+    pub page: usize,
+    /// the uv of the bottom left corner of the glyph.
+    pub uv_min: Vec2,
+    /// the uv of the top right corner of the glyph.
+    pub uv_max: Vec2
+}
+/// a row of glyphs in a page.
+struct Shelf { y: u32, height: u32, x: u32 }
+struct Page {
+    texture: Texture2d,
+    size: u32,
+    shelves: Vec<Shelf>,
+    last_used: u64,
+    generation: u64
+}
+impl Page {
+    fn new(display: &impl Facade, size: u32) -> Result<Self, TextureCreationError> {
+        let texture = Texture2d::with_format(
+            display, blank(size, size), UncompressedFloatFormat::U8, MipmapsOption::NoMipmap
+        )?;
+        Ok(Self { texture, size, shelves: Vec::new(), last_used: 0, generation: next_generation() })
+    }
+    fn clear(&mut self) {
+        self.shelves.clear();
+        self.generation = next_generation();
+        let size = self.size;
+        self.texture.write(Rect { left: 0, bottom: 0, width: size, height: size }, blank(size, size));
+    }
+    /// finds space for a glyph, putting it in the shelf that wastes the least height.
+    fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let (width, height) = (width + PADDING, height + PADDING);
+        let size = self.size;
+        let shelf = self.shelves.iter_mut()
+            .filter(|i| i.height >= height && size - i.x >= width)
+            .min_by_key(|i| i.height);
+        if let Some(shelf) = shelf {
+            shelf.x += width;
+            return Some((shelf.x - width, shelf.y))
+        }
+        let y = self.shelves.last().map_or(0, |i| i.y + i.height);
+        if y + height > size || width > size { return None }
+        self.shelves.push(Shelf { y, height, x: width });
+        Some((0, y))
+    }
+}
+fn blank(width: u32, height: u32) -> RawImage2d<'static, u8> {
+    RawImage2d {
+        data: Cow::Owned(vec![0; (width * height) as usize]),
+        width, height, format: ClientFormat::U8
+    }
+}
+/// glyphs packed into pages of single channel textures. each `Font` has its own atlas.
+pub struct GlyphAtlas {
+    page_size: u32,
+    max_pages: usize,
+    pages: Vec<Page>,
+    glyphs: HashMap<u16, (Metrics, Option<AtlasGlyph>)>,
+    uses: u64
+}
+impl Default for GlyphAtlas {
+    /// pages of 1024 by 1024 pixels with at most 4 pages.
+    fn default() -> Self { Self::new(1024, 4) }
+}
+impl GlyphAtlas {
+    /// creates an atlas with square pages of `page_size` pixels. glyphs bigger than a page get
+    /// a page of their own.
+    pub fn new(page_size: u32, max_pages: usize) -> Self {
+        Self { page_size, max_pages: max_pages.max(1), pages: Vec::new(), glyphs: HashMap::new(), uses: 0 }
+    }
+    /// gets a glyph by its index in the font, marking its page as recently used.
+    pub fn get(&mut self, glyph: u16) -> Option<(Metrics, Option<AtlasGlyph>)> {
+        let found = *self.glyphs.get(&glyph)?;
+        if let (_, Some(AtlasGlyph { page, .. })) = found {
+            self.uses += 1;
+            self.pages[page].last_used = self.uses;
+        }
+        Some(found)
+    }
+    /// gets a glyph without marking it as used.
+    pub fn peek(&self, glyph: u16) -> Option<(Metrics, Option<AtlasGlyph>)> {
+        self.glyphs.get(&glyph).copied()
+    }
+    /// adds a rasterized glyph with rows from top to bottom, as given by fontdue.
+    pub fn insert(
+        &mut self, display: &impl Facade, glyph: u16,
+        metrics: Metrics, bitmap: &[u8]
+    ) -> Result<Option<AtlasGlyph>, TextureCreationError> {
+        if metrics.width == 0 || metrics.height == 0 {
+            self.glyphs.insert(glyph, (metrics, None));
+            return Ok(None)
+        }
+        let (width, height) = (metrics.width as u32, metrics.height as u32);
+        let (page, (x, y)) = self.allocate(display, width, height)?;
+        // OpenGL starts from the bottom row
+        let data = bitmap.chunks_exact(metrics.width).rev().flatten().copied().collect::<Vec<u8>>();
+        let texture = &mut self.pages[page];
+        texture.texture.write(Rect { left: x, bottom: y, width, height }, RawImage2d {
+            data: Cow::Owned(data), width, height, format: ClientFormat::U8
+        });
+        self.uses += 1;
+        texture.last_used = self.uses;
+        let size = texture.size as f32;
+        let region = AtlasGlyph {
+            page,
+            uv_min: vec2(x as f32 / size, y as f32 / size),
+            uv_max: vec2((x + width) as f32 / size, (y + height) as f32 / size)
+        };
+        self.glyphs.insert(glyph, (metrics, Some(region)));
+        Ok(Some(region))
+    }
+    fn allocate(&mut self, display: &impl Facade, width: u32, height: u32) -> Result<(usize, (u32, u32)), TextureCreationError> {
+        for (i, page) in self.pages.iter_mut().enumerate() {
+            if let Some(pos) = page.allocate(width, height) { return Ok((i, pos)) }
+        }
+        let size = self.page_size.max(width.max(height) + PADDING);
+        if self.pages.len() < self.max_pages {
+            let mut page = Page::new(display, size)?;
+            let pos = page.allocate(width, height).expect("a new page fits the glyph");
+            self.pages.push(page);
+            return Ok((self.pages.len() - 1, pos))
+        }
+        // every page is full so the least recently used page is cleared
+        let (i, _) = self.pages.iter().enumerate().min_by_key(|(_, i)| i.last_used).expect("there is at least 1 page");
+        self.glyphs.retain(|_, (_, glyph)| glyph.is_none_or(|glyph| glyph.page != i));
+        if self.pages[i].size < size { self.pages[i] = Page::new(display, size)? }
+        else { self.pages[i].clear() }
+        let pos = self.pages[i].allocate(width, height).expect("a cleared page fits the glyph");
+        Ok((i, pos))
+    }
+    /// the texture of a page. glyphs are stored in the red channel.
+    pub fn page(&self, page: usize) -> Option<&Texture2d> {
+        self.pages.get(page).map(|i| &i.texture)
+    }
+    /// changes whenever a page is cleared or replaced, so uvs kept from before can be checked
+    /// for still pointing at the same glyphs.
+    pub fn generation(&self, page: usize) -> Option<u64> {
+        self.pages.get(page).map(|i| i.generation)
+    }
+    /// the amount of pages.
+    pub fn page_count(&self) -> usize { self.pages.len() }
+    /// the amount of glyphs stored, including ones without a texture like spaces.
+    pub fn len(&self) -> usize { self.glyphs.len() }
+    /// returns true if there are no glyphs stored.
+    pub fn is_empty(&self) -> bool { self.glyphs.is_empty() }
+    /// drops every glyph and page.
+    pub fn clear(&mut self) {
+        self.glyphs.clear();
+        self.pages.clear();
+    }
+}
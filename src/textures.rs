@@ -0,0 +1,123 @@
+//! Loads textures from PNG, JPEG, BMP, GIF, TGA and WebP images.
+//!
+//! Load colour textures made in a paint program as `SrgbTexture2d` so they are converted to
+//! linear colour in the shader, and textures holding data like normal maps as `Texture2d`.
+//! Images are flipped by default so that a uv of (0, 0) is the bottom left, the same as the
+//! meshes in `meshes`.
+//! ```no_run
+//! use thin_engine::{prelude::*, textures::{self, TextureOptions}};
+//! use glium::texture::SrgbTexture2d;
+//! # fn f(display: &Display) -> Result<(), ThinError> {
+//! let albedo: SrgbTexture2d = textures::load(display, "assets/brick.png", TextureOptions::default())?;
+//! let normals: Texture2d = textures::load(display, "assets/brick_normal.png", TextureOptions::default())?;
+//! // e.g. from `include_bytes!()`
+//! # let bytes = [];
+//! let sprite: SrgbTexture2d = textures::load_bytes(
+//!     display, &bytes, TextureOptions::default().with_mipmaps(false).with_premultiplied_alpha(true)
+//! )?;
+//! # Ok(()) }
+//! ```
+use glium::{
+    backend::Facade, Texture2d,
+    texture::{MipmapsOption, RawImage2d, SrgbTexture2d, TextureCreationError}
+};
+use std::path::Path;
+
+/// why a texture failed to load.
+#[derive(Debug)]
+pub enum TextureError {
+    IoError(std::io::Error),
+    /// the image couldn't be decoded, e.g. it is in an unsupported format.
+    ImageError(image::ImageError),
+    TextureCreationError(TextureCreationError)
+}
+impl std::fmt::Display for TextureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IoError(e) => write!(f, "io error: {e}"),
+            Self::ImageError(e) => write!(f, "failed to decode image: {e}"),
+            Self::TextureCreationError(e) => write!(f, "failed to create texture: {e}")
+        }
+    }
+}
+impl std::error::Error for TextureError {}
+impl From<std::io::Error> for TextureError {
+    fn from(err: std::io::Error) -> Self { Self::IoError(err) }
+}
+impl From<image::ImageError> for TextureError {
+    fn from(err: image::ImageError) -> Self { Self::ImageError(err) }
+}
+impl From<TextureCreationError> for TextureError {
+    fn from(err: TextureCreationError) -> Self { Self::TextureCreationError(err) }
+}
+/// how an image is turned into a texture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureOptions {
+    /// generate mipmaps so the texture looks smooth from far away. defaults to true.
+    pub mipmaps: bool,
+    /// flip the image upside down so the top of the image is at a v of 1. defaults to true.
+    pub flip: bool,
+    /// multiply the colour by the alpha, for use with premultiplied alpha blending. defaults
+    /// to false.
+    pub premultiplied_alpha: bool
+}
+impl Default for TextureOptions {
+    fn default() -> Self {
+        Self { mipmaps: true, flip: true, premultiplied_alpha: false }
+    }
+}
+impl TextureOptions {
+    pub fn with_mipmaps(mut self, mipmaps: bool) -> Self {
+        self.mipmaps = mipmaps;
+        self
+    }
+    pub fn with_flip(mut self, flip: bool) -> Self {
+        self.flip = flip;
+        self
+    }
+    pub fn with_premultiplied_alpha(mut self, premultiplied_alpha: bool) -> Self {
+        self.premultiplied_alpha = premultiplied_alpha;
+        self
+    }
+}
+/// a texture that can be loaded from an image. `SrgbTexture2d` is loaded as srgb colour and
+/// `Texture2d` as linear data.
+pub trait LoadTexture: Sized {
+    fn from_image(display: &impl Facade, image: RawImage2d<u8>, mipmaps: MipmapsOption) -> Result<Self, TextureCreationError>;
+}
+impl LoadTexture for Texture2d {
+    fn from_image(display: &impl Facade, image: RawImage2d<u8>, mipmaps: MipmapsOption) -> Result<Self, TextureCreationError> {
+        Texture2d::with_mipmaps(display, image, mipmaps)
+    }
+}
+impl LoadTexture for SrgbTexture2d {
+    fn from_image(display: &impl Facade, image: RawImage2d<u8>, mipmaps: MipmapsOption) -> Result<Self, TextureCreationError> {
+        SrgbTexture2d::with_mipmaps(display, image, mipmaps)
+    }
+}
+/// decodes an image into RGBA pixels ready to upload, guessing the format from its contents.
+pub fn decode(bytes: &[u8], options: TextureOptions) -> Result<RawImage2d<'static, u8>, TextureError> {
+    let mut image = image::load_from_memory(bytes)?.into_rgba8();
+    let size = image.dimensions();
+    if options.premultiplied_alpha {
+        for pixel in image.pixels_mut() {
+            let alpha = pixel[3] as u32;
+            for channel in &mut pixel.0[..3] { *channel = ((*channel as u32 * alpha + 127) / 255) as u8 }
+        }
+    }
+    // OpenGL starts from the bottom row so the rows are flipped unless asked not to
+    let mut pixels = image.into_raw();
+    if options.flip {
+        pixels = pixels.chunks_exact(size.0 as usize * 4).rev().flatten().copied().collect();
+    }
+    Ok(RawImage2d::from_raw_rgba(pixels, size))
+}
+/// loads a texture from an image in memory, e.g. from `include_bytes!()`.
+pub fn load_bytes<T: LoadTexture>(display: &impl Facade, bytes: &[u8], options: TextureOptions) -> Result<T, TextureError> {
+    let mipmaps = if options.mipmaps { MipmapsOption::AutoGeneratedMipmaps } else { MipmapsOption::NoMipmap };
+    Ok(T::from_image(display, decode(bytes, options)?, mipmaps)?)
+}
+/// loads a texture from an image file.
+pub fn load<T: LoadTexture>(display: &impl Facade, path: impl AsRef<Path>, options: TextureOptions) -> Result<T, TextureError> {
+    load_bytes(display, &std::fs::read(path)?, options)
+}
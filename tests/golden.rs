@@ -0,0 +1,40 @@
+use thin_engine::{prelude::*, golden::{self, Golden}, meshes::screen};
+
+#[test]
+fn fxaa() {
+    let image = golden::render(64, 64, |display, frame| {
+        let (indices, vertices, uvs) = mesh!(
+            display, &screen::INDICES, &screen::VERTICES, &screen::UVS
+        )?;
+        let checker = golden::checkerboard(64, 64, 8).to_texture(display)?;
+        let fxaa = shaders::fxaa_shader(display)?;
+        frame.draw(
+            (&vertices, &uvs), &indices, &fxaa,
+            &shaders::fxaa_uniforms(&checker), &DrawParameters::default()
+        )?;
+        Ok(())
+    }).unwrap();
+    Golden::new("tests/golden/fxaa.png").with_tolerance(2).assert(&image);
+}
+#[cfg(feature = "text")]
+#[test]
+fn font_shader() {
+    use thin_engine::text_renderer::*;
+    let image = golden::render(128, 32, |display, frame| {
+        let mut font = Font::from_scale_and_file(24.0, "examples/DroidSans.ttf").map_err(ThinError::FontError)?;
+        let (indices, vertices, uvs) = Font::mesh(display)?;
+        let shader = Font::shader(display)?;
+        let draw_params = DrawParameters { blend: glium::Blend::alpha_blending(), ..Default::default() };
+        let text_renderer = TextRenderer {
+            shader: &shader, indices: &indices, vertices: &vertices, uvs: &uvs,
+            draw_params: &draw_params, display
+        };
+        text_renderer.draw(
+            "Golden", vec3(1.0, 0.8, 0.2), frame,
+            Mat4::from_pos_and_scale(vec3(-3.8, 0.9, 0.0), Vec3::splat(1.6)),
+            Mat4::perspective_2d((128, 32)), Mat4::default(), &mut font
+        )?;
+        Ok(())
+    }).unwrap();
+    Golden::new("tests/golden/font.png").with_tolerance(2).assert(&image);
+}
@@ -0,0 +1,96 @@
+use thin_engine::{prelude::*, headless::*};
+#[derive(Hash, PartialEq, Eq, Clone, Copy)]
+enum Action { Jump }
+
+#[test]
+fn scripted_input_and_capture() {
+    let input = input_map!((Action::Jump, KeyCode::Space));
+    let run = thin_engine::headless::builder(input)
+        .with_size(16, 16)
+        .with_frames(6)
+        .with_capture(Capture::Every(2))
+        .with_script(InputScript::new().tap(1, KeyCode::Space).tap(3, KeyCode::Space))
+        .with_setup(|_, _| Ok(0))
+        .with_update(|jumps, input, _, _, _| {
+            if input.pressed(Action::Jump) { *jumps += 1 }
+            Ok(())
+        })
+        .with_draw(|jumps, frame, _, _, _, _| {
+            frame.clear_color(*jumps as f32 / 2.0, 0.0, 1.0, 1.0);
+            Ok(())
+        })
+        .run().unwrap();
+    assert_eq!(run.state, 2);
+    assert_eq!(run.time.frame, 6);
+    assert_eq!(run.frames.iter().map(|(i, _)| *i).collect::<Vec<_>>(), [0, 2, 4]);
+    assert_eq!(run.frame(0).unwrap().pixel(0, 0), [0, 0, 255, 255]);
+    assert_eq!(run.last_frame().unwrap().pixel(15, 15), [255, 0, 255, 255]);
+}
+#[test]
+fn errors_stop_the_run() {
+    let result = thin_engine::headless::builder(input_map!())
+        .with_size(4, 4)
+        .with_frames(10)
+        .with_update(|_, _, _, _, time| match time.frame {
+            3 => Err(ThinError::Other("stopped".into())),
+            _ => Ok(())
+        })
+        .run();
+    assert!(matches!(result, Err(ThinError::Other(_))));
+}
+/// runs an unchanged `ThinBuilder` program. it makes a real window so it needs a display.
+#[test]
+#[ignore = "needs a display, run with `xvfb-run cargo test --features headless -- --ignored`"]
+fn builder_program() {
+    #[cfg(target_os = "linux")]
+    use winit::platform::{x11::EventLoopBuilderExtX11, wayland::EventLoopBuilderExtWayland};
+    let mut builder = EventLoop::builder();
+    // tests aren't run on the main thread
+    #[cfg(target_os = "linux")]
+    {
+        EventLoopBuilderExtX11::with_any_thread(&mut builder, true);
+        EventLoopBuilderExtWayland::with_any_thread(&mut builder, true);
+    }
+    let event_loop = builder.build().unwrap();
+    let input = input_map!((Action::Jump, KeyCode::Space));
+    let run = thin_engine::builder(input)
+        .with_window_settings(SimpleWindowBuilder::new().with_inner_size(32, 32))
+        .with_replay(InputScript::new().tap(2, KeyCode::Space))
+        .with_setup(|_, _, _, _, _| Ok(0))
+        .with_update(|jumps, input, display, _, _, window, _| {
+            if input.pressed(Action::Jump) { *jumps += 1 }
+            let mut frame = display.draw();
+            frame.clear_color(0.0, 1.0, 0.0, 1.0);
+            window.pre_present_notify();
+            frame.finish()?;
+            Ok(())
+        })
+        .build_headless(event_loop, 5, Capture::Last).unwrap();
+    assert_eq!(run.state, 1);
+    assert_eq!(run.time.frame, 5);
+    assert_eq!(run.frame(4).unwrap().pixel(0, 0), [0, 255, 0, 255]);
+}
+#[cfg(feature = "text")]
+#[test]
+fn batch_with_evicted_atlas_page() {
+    use thin_engine::text_renderer::{*, atlas::GlyphAtlas};
+    let display = Headless::new((64, 64)).unwrap();
+    let texture = Texture2d::empty(&display, 64, 64).unwrap();
+    let mut frame = glium::framebuffer::SimpleFrameBuffer::new(&display, &texture).unwrap();
+    // a single page that only fits a few glyphs
+    let mut font = Font::from_scale_and_file(24.0, "examples/DroidSans.ttf").unwrap()
+        .with_atlas(GlyphAtlas::new(40, 1));
+    let shader = TextBatch::shader(&display).unwrap();
+    let (model, draw_params) = (Mat4::default(), DrawParameters::default());
+    let mut batch = TextBatch::new();
+    batch.add("ab", Vec3::ONE, model, &mut font, &display).unwrap();
+    batch.draw(&mut frame, &display, &shader, &font, model, model, &draw_params).unwrap();
+
+    batch.add("cdefghijk", Vec3::ONE, model, &mut font, &display).unwrap();
+    let result = batch.draw(&mut frame, &display, &shader, &font, model, model, &draw_params);
+    assert!(matches!(result, Err(TextDrawError::StaleAtlasPage(0))));
+
+    batch.clear();
+    batch.add("k", Vec3::ONE, model, &mut font, &display).unwrap();
+    batch.draw(&mut frame, &display, &shader, &font, model, model, &draw_params).unwrap();
+}